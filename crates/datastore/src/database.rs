@@ -13,6 +13,11 @@ struct DatabaseState {
     /// Maps table name to statements to load from the table.
     load_statements: HashMap<String, String>,
 
+    /// Maps table name to statements checking whether a row with a given primary key exists.
+    ///
+    /// Tables without primary keys have no entry.
+    exists_statements: HashMap<String, String>,
+
     descriptor: DatabaseDescriptor,
 }
 
@@ -21,12 +26,137 @@ pub struct Database {
     state: DatabaseState,
 }
 
+/// A database opened with [Database::open_readonly].
+///
+/// Only allows reading: there is no way to get a [Transaction] from one, only a [ReadTransaction].
+pub struct ReadOnlyDatabase {
+    inner: Database,
+}
+
+/// A transaction against a [ReadOnlyDatabase], which only exposes the operations that don't write.
+///
+/// Reads work as on a [Transaction]:
+///
+/// ```
+/// fn read(txn: &ammo_datastore::ReadTransaction) {
+///     let _ = txn.row_exists("schema", "table", 1u32);
+/// }
+/// ```
+///
+/// But there is no way to write:
+///
+/// ```compile_fail
+/// fn write(mut txn: ammo_datastore::ReadTransaction) {
+///     let _ = txn.patch_table("schema", "table", &[1u32]);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// fn write(txn: ammo_datastore::ReadTransaction) {
+///     let _ = txn.truncate_table("schema", "table");
+/// }
+/// ```
+///
+/// ```compile_fail
+/// fn write(txn: ammo_datastore::ReadTransaction) {
+///     let _ = txn.commit();
+/// }
+/// ```
+pub struct ReadTransaction<'a> {
+    inner: Transaction<'a>,
+}
+
+/// A prepared load from a table, from [Transaction::prepare_load].
+///
+/// Holds the statement, so that [PreparedLoad::iter] can borrow from it.
+pub struct PreparedLoad<'a> {
+    table: &'a crate::TableDescriptor,
+    statement: rusqlite::CachedStatement<'a>,
+}
+
+/// Output formats for [Transaction::export_table].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// RFC 4180 CSV, with a header row of column names.
+    Csv,
+    /// One JSON object per line.
+    JsonLines,
+}
+
+/// Receives rows from [Transaction::load_all_tables].
+pub trait TableVisitor {
+    /// Called once per row.  Returning an error stops the load.
+    fn visit_row(
+        &mut self,
+        schema: &str,
+        table: &str,
+        row: crate::row_value::RowValue,
+    ) -> Result<()>;
+}
+
 /// A transaction like that from rusqlite: drop rolls back, calling commit commits.
 pub struct Transaction<'a> {
     state: &'a DatabaseState,
     transaction: rusqlite::Transaction<'a>,
 }
 
+/// A [Transaction] scoped to one schema, from [Transaction::schema].
+///
+/// The methods are those of the transaction, but take only the table name.
+pub struct SchemaTransaction<'t, 'a> {
+    transaction: &'t mut Transaction<'a>,
+    schema: &'a str,
+}
+
+/// A row of the migrations table, recording a migration which has been run against this database.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MigrationRecord {
+    /// Historically never written, so this is usually `None`.
+    pub id: Option<i64>,
+    pub schema: String,
+    pub name: String,
+    /// The sql which ran, after template rendering.
+    pub sql: String,
+    /// Unix timestamp in seconds.
+    pub ran_at: Option<f64>,
+    /// How long the migration took, in seconds.
+    pub duration: f64,
+}
+
+/// A difference between a table's descriptor and the table in the database, from [Database::check_schema_drift].
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum DriftIssue {
+    /// The descriptor declares a table which doesn't exist.
+    MissingTable { schema: String, table: String },
+    /// The descriptor declares a column which the table doesn't have.
+    MissingColumn {
+        schema: String,
+        table: String,
+        column: String,
+    },
+    /// The table has a column which the descriptor doesn't declare.
+    ExtraColumn {
+        schema: String,
+        table: String,
+        column: String,
+    },
+    /// The column's declared sql type doesn't have the affinity the descriptor's column type needs.
+    TypeMismatch {
+        schema: String,
+        table: String,
+        column: String,
+        expected: crate::ColumnType,
+        actual: String,
+    },
+    /// The column's nullability doesn't match the descriptor.
+    NullabilityMismatch {
+        schema: String,
+        table: String,
+        column: String,
+        descriptor_nullable: bool,
+    },
+}
+
 /// SQL that we run as part of opening a connection.
 ///
 /// - Sets up WAL.
@@ -34,17 +164,40 @@ pub struct Transaction<'a> {
 /// - Makes sure the WAL file is truncated because they can grow quite large under some obscure conditions that are way
 ///   too involved to stick in anything less than a full blog post.
 /// - Enables foreign key enforcement (though we don't expect foreign keys to be used).
-/// - Raises the default cache size because the one sqlite sets up for us is only a couple megabytes since they have to
-///   make their default swork with e.g. phones.
-const INITIAL_SQL: &str = r#"
-PRAGMA busy_timeout = 1000;
-PRAGMA cache_size = -100000;
+/// - Applies the rest of the tunables from the [crate::PragmaConfig], e.g. raising the cache size.
+const INITIAL_SQL_TEMPLATE: &str = r#"
+PRAGMA busy_timeout = {{ busy_timeout }};
+PRAGMA cache_size = {{ cache_size }};
 PRAGMA foreign_keys = 1;
 pragma journal_mode = WAL;
+{% if mmap_size is defined %}PRAGMA mmap_size = {{ mmap_size }};{% endif %}
+{% if synchronous is defined %}PRAGMA synchronous = {{ synchronous }};{% endif %}
 PRAGMA wal_autocheckpoint = 10000;
 PRAGMA wal_checkpoint(full);
 "#;
 
+/// Render [INITIAL_SQL_TEMPLATE] for a given database.
+fn build_initial_sql(descriptor: &DatabaseDescriptor) -> Result<String> {
+    let config = descriptor.get_pragma_config();
+    let mut context = tera::Context::new();
+    context.insert("busy_timeout", &config.busy_timeout_ms);
+    context.insert("cache_size", &config.cache_size);
+    if let Some(mmap_size) = config.mmap_size {
+        context.insert("mmap_size", &mmap_size);
+    }
+    if let Some(synchronous) = config.synchronous {
+        context.insert("synchronous", synchronous.as_sql());
+    }
+
+    let sql = tera::Tera::one_off(INITIAL_SQL_TEMPLATE, &context, false)?;
+    debug!(
+        "Initial SQL for {}: {}",
+        descriptor.get_path().display(),
+        sql
+    );
+    Ok(sql)
+}
+
 /// Returns an iterator `(schemaname, table_descriptor)`.
 fn iter_all_tables(
     descriptor: &DatabaseDescriptor,
@@ -63,12 +216,51 @@ const INSERT_TEMPLATE: &str = r#"
 INSERT {% if has_pk %}OR REPLACE{% endif %} INTO {{ table}}(
     {{ columns | join(sep=", ") }}
 ) values (
-    {%- for c in columns -%}
-    :{{ c}}{% if not loop.last %},{% endif -%}
-    {%- endfor -%}
+    {{ values | join(sep=", ") }}
 )
 "#;
 
+/// The current time as unix seconds, in sql.  `julianday` is used over `unixepoch` because the latter is too new for
+/// the bundled sqlite.
+const SQL_NOW: &str = "((julianday('now') - 2440587.5) * 86400.0)";
+
+/// Build the expression for the value of a column in an insert statement.
+///
+/// Most columns are just their parameter.  Columns with defaults are bound as null when the row omits them, so they
+/// coalesce first to the value in the row being replaced, if any, and then to the default.
+fn build_insert_value(
+    table_ident: &str,
+    table: &crate::TableDescriptor,
+    column: &crate::ColumnDescriptor,
+) -> String {
+    use itertools::Itertools;
+
+    let default = match column.get_default() {
+        None => return format!(":{}", column.get_name()),
+        Some(crate::ColumnDefault::CurrentTimestamp) => SQL_NOW,
+    };
+
+    let pk_columns = table
+        .iter_columns()
+        .filter(|x| x.is_primary_key())
+        .collect::<Vec<_>>();
+    if pk_columns.is_empty() {
+        return format!("COALESCE(:{}, {})", column.get_name(), default);
+    }
+
+    let pk_cond = pk_columns
+        .iter()
+        .map(|x| format!("{0} = :{0}", x.get_name()))
+        .join(" AND ");
+    format!(
+        "COALESCE(:{0}, (SELECT {0} FROM {1} WHERE {2}), {3})",
+        column.get_name(),
+        table_ident,
+        pk_cond,
+        default
+    )
+}
+
 /// Build the insert statements for all schemas and tables in the database.
 fn build_insert_statements(descriptor: &DatabaseDescriptor) -> Result<HashMap<String, String>> {
     let mut ret: HashMap<String, String> = Default::default();
@@ -84,6 +276,13 @@ fn build_insert_statements(descriptor: &DatabaseDescriptor) -> Result<HashMap<St
                 .map(|x| x.get_name())
                 .collect::<Vec<_>>(),
         );
+        context.insert(
+            "values",
+            &table
+                .iter_columns()
+                .map(|x| build_insert_value(&table_ident, table, x))
+                .collect::<Vec<_>>(),
+        );
         context.insert("has_pk", &table.iter_columns().any(|x| x.is_primary_key()));
 
         let stmt = tera::Tera::one_off(INSERT_TEMPLATE, &context, false)?;
@@ -123,12 +322,56 @@ fn build_load_statements(descriptor: &DatabaseDescriptor) -> Result<HashMap<Stri
     Ok(ret)
 }
 
+const EXISTS_TEMPLATE: &str = r#"
+SELECT 1 FROM {{ table }}
+WHERE {% for c in pk_columns %}{{ c }} = :{{ c }}{% if not loop.last %} AND {% endif %}{% endfor %}
+LIMIT 1
+"#;
+
+/// Build the statements which check for the existence of a row by primary key.
+fn build_exists_statements(descriptor: &DatabaseDescriptor) -> Result<HashMap<String, String>> {
+    let mut ret: HashMap<String, String> = Default::default();
+
+    for (schema, table) in iter_all_tables(descriptor) {
+        let pk_columns = table
+            .iter_columns()
+            .filter(|x| x.is_primary_key())
+            .map(|x| x.get_name())
+            .collect::<Vec<_>>();
+        if pk_columns.is_empty() {
+            continue;
+        }
+
+        let table_ident = build_table_ident(schema, table.get_name());
+        let mut context = tera::Context::new();
+        context.insert("table", &table_ident);
+        context.insert("pk_columns", &pk_columns);
+
+        let stmt = tera::Tera::one_off(EXISTS_TEMPLATE, &context, false)?;
+        debug!("Exists statement for {}: {}", table_ident, stmt);
+        ret.insert(table_ident, stmt);
+    }
+
+    Ok(ret)
+}
+
 /// Run the migrations for a given database, creating the initial migrations infrastructure if necessary.
 ///
 /// Note that the initial migrations table is, in effect, the only thing we can't migrate without a lot of work.
 fn run_migrations(conn: &mut rusqlite::Connection, descriptor: &DatabaseDescriptor) -> Result<()> {
     let transaction = conn.transaction()?;
 
+    // Refuse to touch a database written by a newer version of the application.
+    let db_version = read_schema_version(&transaction)?;
+    if db_version > descriptor.get_schema_version() {
+        anyhow::bail!(
+            "Database {} is at schema version {}, but this binary only knows about version {}",
+            descriptor.get_path().display(),
+            db_version,
+            descriptor.get_schema_version()
+        );
+    }
+
     // First, we create our migrations table.
     transaction.execute(r#"CREATE TABLE IF NOT EXISTS migrations (
         -- Incrementing, unique id for the migration.
@@ -193,10 +436,200 @@ fn run_migrations(conn: &mut rusqlite::Connection, descriptor: &DatabaseDescript
     transaction.execute("PRAGMA FOREIGN_KEY_CHECK", [])?;
     transaction.execute("PRAGMA foreign_keys = 1", [])?;
 
+    // The version commits along with the migrations which brought us up to it.
+    if db_version != descriptor.get_schema_version() {
+        store_schema_version(&transaction, descriptor.get_schema_version())?;
+    }
+
     transaction.commit()?;
     Ok(())
 }
 
+/// Check that every migration in the descriptor has already been run against this connection, without running
+/// anything.
+fn check_migrations(conn: &rusqlite::Connection, descriptor: &DatabaseDescriptor) -> Result<()> {
+    let mut statement = conn.prepare("SELECT * FROM migrations where schema = ? AND name = ?")?;
+
+    for schema in descriptor.iter_schemas() {
+        for mig in schema.iter_migrations() {
+            if !statement.exists(rusqlite::params![schema.get_name(), mig.get_name()])? {
+                anyhow::bail!(
+                    "Database {} hasn't run migration {} for schema {}",
+                    descriptor.get_path().display(),
+                    mig.get_name(),
+                    schema.get_name()
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The sqlite type affinity of a declared column type, following the rules in section 3.1 of
+/// <https://www.sqlite.org/datatype3.html>.
+fn sqlite_affinity(declared: &str) -> &'static str {
+    let declared = declared.to_ascii_uppercase();
+    if declared.contains("INT") {
+        "INTEGER"
+    } else if ["CHAR", "CLOB", "TEXT"]
+        .iter()
+        .any(|x| declared.contains(x))
+    {
+        "TEXT"
+    } else if declared.is_empty() || declared.contains("BLOB") {
+        "BLOB"
+    } else if ["REAL", "FLOA", "DOUB"]
+        .iter()
+        .any(|x| declared.contains(x))
+    {
+        "REAL"
+    } else {
+        "NUMERIC"
+    }
+}
+
+/// Compare one table against the live database, appending any issues.
+fn check_table_drift(
+    conn: &rusqlite::Connection,
+    schema: &str,
+    table: &crate::TableDescriptor,
+    issues: &mut Vec<DriftIssue>,
+) -> Result<()> {
+    use crate::ColumnType;
+
+    // name -> (declared type, not null, part of the primary key).
+    let mut statement = conn.prepare(&format!(
+        "PRAGMA table_info({})",
+        build_table_ident(schema, table.get_name())
+    ))?;
+    let mut live = statement
+        .query_map([], |r| {
+            Ok((
+                r.get::<_, String>("name")?,
+                (
+                    r.get::<_, String>("type")?,
+                    r.get::<_, bool>("notnull")?,
+                    r.get::<_, i64>("pk")? != 0,
+                ),
+            ))
+        })?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+
+    // Only a lone `INTEGER PRIMARY KEY` column is the rowid, which can never be null even though sqlite doesn't report it
+    // as `NOT NULL`.  Other primary keys, including composite ones, do accept null unless declared otherwise.
+    let mut live_pks = live.iter().filter(|(_, (_, _, pk))| *pk);
+    let rowid_alias = match (live_pks.next(), live_pks.next()) {
+        (Some((name, (declared, _, _))), None) if declared.eq_ignore_ascii_case("INTEGER") => {
+            Some(name.clone())
+        }
+        _ => None,
+    };
+
+    if live.is_empty() {
+        issues.push(DriftIssue::MissingTable {
+            schema: schema.to_string(),
+            table: table.get_name().to_string(),
+        });
+        return Ok(());
+    }
+
+    for c in table.iter_columns() {
+        let column = c.get_name().to_string();
+        let (declared, not_null, _) = match live.remove(&column) {
+            Some(x) => x,
+            None => {
+                issues.push(DriftIssue::MissingColumn {
+                    schema: schema.to_string(),
+                    table: table.get_name().to_string(),
+                    column,
+                });
+                continue;
+            }
+        };
+
+        let expected_affinity = match c.get_column_type() {
+            ColumnType::I64 => "INTEGER",
+            ColumnType::F64 => "REAL",
+            ColumnType::String | ColumnType::Json => "TEXT",
+        };
+        if sqlite_affinity(&declared) != expected_affinity {
+            issues.push(DriftIssue::TypeMismatch {
+                schema: schema.to_string(),
+                table: table.get_name().to_string(),
+                column: column.clone(),
+                expected: *c.get_column_type(),
+                actual: declared,
+            });
+        }
+
+        let live_nullable = !not_null && rowid_alias.as_ref() != Some(&column);
+        if live_nullable != c.is_nullable() {
+            issues.push(DriftIssue::NullabilityMismatch {
+                schema: schema.to_string(),
+                table: table.get_name().to_string(),
+                column,
+                descriptor_nullable: c.is_nullable(),
+            });
+        }
+    }
+
+    let mut extra = live.into_keys().collect::<Vec<_>>();
+    extra.sort();
+    issues.extend(extra.into_iter().map(|column| DriftIssue::ExtraColumn {
+        schema: schema.to_string(),
+        table: table.get_name().to_string(),
+        column,
+    }));
+
+    Ok(())
+}
+
+/// Read the schema version last recorded in this database.  Databases which have never recorded one are at version 0.
+fn read_schema_version(conn: &rusqlite::Connection) -> Result<u32> {
+    use rusqlite::OptionalExtension;
+
+    let has_table = conn
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'schema_version'")?
+        .exists([])?;
+    if !has_table {
+        return Ok(0);
+    }
+
+    let version: Option<u32> = conn
+        .query_row("SELECT version FROM schema_version WHERE id = 0", [], |r| {
+            r.get(0)
+        })
+        .optional()?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Record the descriptor's schema version, once migrations have brought the database up to it.
+fn store_schema_version(conn: &rusqlite::Connection, version: u32) -> Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version (
+            id INTEGER PRIMARY KEY CHECK (id = 0),
+            version INTEGER NOT NULL
+        )",
+    )?;
+    conn.execute(
+        "INSERT OR REPLACE INTO schema_version(id, version) VALUES(0, ?)",
+        [version],
+    )?;
+    Ok(())
+}
+
+impl DatabaseState {
+    fn new(descriptor: DatabaseDescriptor) -> Result<Self> {
+        Ok(DatabaseState {
+            load_statements: build_load_statements(&descriptor)?,
+            insert_statements: build_insert_statements(&descriptor)?,
+            exists_statements: build_exists_statements(&descriptor)?,
+            descriptor,
+        })
+    }
+}
+
 impl Database {
     pub fn open(descriptor: DatabaseDescriptor) -> Result<Self> {
         use itertools::Itertools;
@@ -221,20 +654,98 @@ impl Database {
         mut conn: rusqlite::Connection,
         descriptor: DatabaseDescriptor,
     ) -> Result<Self> {
-        let load_statements = build_load_statements(&descriptor)?;
-        let insert_statements = build_insert_statements(&descriptor)?;
-        conn.execute_batch(INITIAL_SQL)?;
+        conn.execute_batch(&build_initial_sql(&descriptor)?)?;
         run_migrations(&mut conn, &descriptor)?;
         Ok(Database {
-            state: DatabaseState {
-                load_statements,
-                insert_statements,
-                descriptor,
-            },
+            state: DatabaseState::new(descriptor)?,
             conn,
         })
     }
 
+    /// Open a database without the ability to write to it, e.g. for inspecting a copy of a production database.
+    ///
+    /// This doesn't run migrations.  Instead, it errors if any migration in the descriptor hasn't already been run,
+    /// since the tables may not match what the descriptor says they are.  WAL and the other pragmas that would write
+    /// aren't touched either, but the cache size and busy timeout from the [crate::PragmaConfig] still apply.
+    pub fn open_readonly(descriptor: DatabaseDescriptor) -> Result<ReadOnlyDatabase> {
+        let path = descriptor.get_path().join("database.sqlite");
+        info!("Opening database read-only at {}", path.display());
+        let conn = rusqlite::Connection::open_with_flags(
+            &path,
+            rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY | rusqlite::OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+
+        let config = descriptor.get_pragma_config();
+        conn.execute_batch(&format!(
+            "PRAGMA busy_timeout = {}; PRAGMA cache_size = {};",
+            config.busy_timeout_ms, config.cache_size
+        ))?;
+        check_migrations(&conn, &descriptor)?;
+        // We can't record a new version, so the database must already be exactly at ours.
+        let db_version = read_schema_version(&conn)?;
+        if db_version != descriptor.get_schema_version() {
+            anyhow::bail!(
+                "Database {} is at schema version {}, but the descriptor is at version {}",
+                descriptor.get_path().display(),
+                db_version,
+                descriptor.get_schema_version()
+            );
+        }
+
+        Ok(ReadOnlyDatabase {
+            inner: Database {
+                state: DatabaseState::new(descriptor)?,
+                conn,
+            },
+        })
+    }
+
+    /// Get the history of all migrations which have been run against this database, oldest first.
+    pub fn migration_history(&self) -> Result<Vec<MigrationRecord>> {
+        let mut statement = self.conn.prepare(
+            "SELECT id, schema, name, sql, ran_at, duration FROM migrations ORDER BY ran_at, rowid",
+        )?;
+        let records = statement
+            .query_map([], |r| {
+                Ok(MigrationRecord {
+                    id: r.get(0)?,
+                    schema: r.get(1)?,
+                    name: r.get(2)?,
+                    sql: r.get(3)?,
+                    ran_at: r.get(4)?,
+                    duration: r.get(5)?,
+                })
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+
+    /// Compare the descriptor against the tables actually in the database, returning every difference found.
+    ///
+    /// Since table creation is left to migrations, nothing else catches a descriptor which doesn't match what the
+    /// migrations built.  Tables are checked in order of `(schema, table)`, so the result is deterministic.
+    pub fn check_schema_drift(&self) -> Result<Vec<DriftIssue>> {
+        let mut tables = iter_all_tables(&self.state.descriptor).collect::<Vec<_>>();
+        tables.sort_unstable_by_key(|(schema, table)| (*schema, table.get_name()));
+
+        let mut issues = vec![];
+        for (schema, table) in tables {
+            check_table_drift(&self.conn, schema, table, &mut issues)?;
+        }
+        Ok(issues)
+    }
+
+    /// Run arbitrary sql against the underlying connection, for the queries this crate doesn't cover.
+    ///
+    /// This is an escape hatch, mostly for analytics.  Callers must not change the schema in ways that the descriptor
+    /// doesn't know about: the prebuilt statements won't know either.
+    pub fn with_raw_connection<R>(
+        &self,
+        f: impl FnOnce(&rusqlite::Connection) -> Result<R>,
+    ) -> Result<R> {
+        f(&self.conn)
+    }
+
     pub fn transaction(&mut self) -> Result<Transaction> {
         Ok(Transaction {
             state: &self.state,
@@ -243,6 +754,115 @@ impl Database {
     }
 }
 
+impl<'a> PreparedLoad<'a> {
+    /// Iterate over the rows of the table.
+    ///
+    /// Each call to this function runs the query again from the beginning.
+    pub fn iter<T: serde::de::DeserializeOwned>(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<T>> + '_> {
+        self.query(&[])
+    }
+
+    /// Like [PreparedLoad::iter], but yields the rows without deserializing them.
+    pub fn iter_rows(
+        &mut self,
+    ) -> Result<impl Iterator<Item = Result<crate::row_value::RowValue>> + '_> {
+        self.query_rows(&[])
+    }
+
+    /// Run the statement with the given named parameters, deserializing each row.
+    fn query<'s, T: serde::de::DeserializeOwned>(
+        &'s mut self,
+        params: &[(&str, &dyn rusqlite::ToSql)],
+    ) -> Result<impl Iterator<Item = Result<T>> + 's> {
+        Ok(self
+            .query_rows(params)?
+            .map(|r| r.and_then(|rv| rv.deserialize::<T>())))
+    }
+
+    /// Run the statement with the given named parameters.
+    fn query_rows<'s>(
+        &'s mut self,
+        params: &[(&str, &dyn rusqlite::ToSql)],
+    ) -> Result<impl Iterator<Item = Result<crate::row_value::RowValue>> + 's> {
+        let table: &crate::TableDescriptor = self.table;
+        let rows = self.statement.query_map(params, move |r| {
+            Ok(crate::row_value::RowValue::from_rusqlite_row(table, r))
+        })?;
+        Ok(rows.map(|r| r?))
+    }
+}
+
+impl ReadOnlyDatabase {
+    pub fn transaction(&mut self) -> Result<ReadTransaction> {
+        Ok(ReadTransaction {
+            inner: self.inner.transaction()?,
+        })
+    }
+
+    pub fn migration_history(&self) -> Result<Vec<MigrationRecord>> {
+        self.inner.migration_history()
+    }
+}
+
+impl<'a> ReadTransaction<'a> {
+    /// See [Transaction::load_table].
+    pub fn load_table<T: serde::de::DeserializeOwned>(
+        &self,
+        schema: &str,
+        table: &str,
+        callback: impl FnMut(T) -> Result<()>,
+    ) -> Result<()> {
+        self.inner.load_table(schema, table, callback)
+    }
+
+    /// See [Transaction::row_exists].
+    pub fn row_exists<K: serde::Serialize>(
+        &self,
+        schema: &str,
+        table: &str,
+        key: K,
+    ) -> Result<bool> {
+        self.inner.row_exists(schema, table, key)
+    }
+
+    /// See [Transaction::prepare_load].
+    pub fn prepare_load(&self, schema: &str, table: &str) -> Result<PreparedLoad> {
+        self.inner.prepare_load(schema, table)
+    }
+
+    /// See [Transaction::load_table_json_filter].
+    pub fn load_table_json_filter<T: serde::de::DeserializeOwned, V: serde::Serialize>(
+        &self,
+        schema: &str,
+        table: &str,
+        json_column: &str,
+        path: &str,
+        value: V,
+        callback: impl FnMut(T) -> Result<()>,
+    ) -> Result<()> {
+        self.inner
+            .load_table_json_filter(schema, table, json_column, path, value, callback)
+    }
+
+    /// See [Transaction::load_all_tables].
+    pub fn load_all_tables(&self, visitor: &mut dyn TableVisitor) -> Result<()> {
+        self.inner.load_all_tables(visitor)
+    }
+
+    /// See [Transaction::export_table].
+    pub fn export_table(
+        &self,
+        schema: &str,
+        table: &str,
+        format: ExportFormat,
+        out: &mut dyn std::io::Write,
+    ) -> Result<()> {
+        self.inner.export_table(schema, table, format, out)
+    }
+}
+
 impl<'a> Transaction<'a> {
     /// Load a table, calling the user-specified function with each returned row.
     ///
@@ -254,23 +874,172 @@ impl<'a> Transaction<'a> {
         table: &str,
         mut callback: impl FnMut(T) -> Result<()>,
     ) -> Result<()> {
+        let mut load = self.prepare_load(schema, table)?;
+        for row in load.iter()? {
+            callback(row?)?;
+        }
+
+        Ok(())
+    }
+
+    /// Load every table in the database, passing each row to the visitor.
+    ///
+    /// Tables are visited one at a time, but in no particular order.
+    pub fn load_all_tables(&self, visitor: &mut dyn TableVisitor) -> Result<()> {
+        for (schema, table) in iter_all_tables(&self.state.descriptor) {
+            let mut load = self.prepare_load(schema, table.get_name())?;
+            for row in load.iter_rows()? {
+                visitor.visit_row(schema, table.get_name(), row?)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Write the contents of a table to `out`, one row at a time.
+    ///
+    /// JSON columns are embedded as JSON in [ExportFormat::JsonLines], and as their serialized text in
+    /// [ExportFormat::Csv].
+    pub fn export_table(
+        &self,
+        schema: &str,
+        table: &str,
+        format: ExportFormat,
+        out: &mut dyn std::io::Write,
+    ) -> Result<()> {
+        let mut load = self.prepare_load(schema, table)?;
+
+        if format == ExportFormat::Csv {
+            for (i, c) in load.table.iter_columns().enumerate() {
+                if i != 0 {
+                    out.write_all(b",")?;
+                }
+                crate::row_value::write_csv_field(out, c.get_name())?;
+            }
+            out.write_all(b"\r\n")?;
+        }
+
+        for row in load.iter_rows()? {
+            let row = row?;
+            match format {
+                ExportFormat::Csv => row.write_csv_record(out)?,
+                ExportFormat::JsonLines => {
+                    serde_json::to_writer(&mut *out, &row.into_json())?;
+                    out.write_all(b"\n")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Prepare to load a table lazily, via [PreparedLoad::iter].
+    ///
+    /// This is two steps instead of one because the iterator borrows the statement, so the statement has to live
+    /// somewhere.  In return, rows are only read from the database as the iterator advances, so e.g. `.take(n)` only
+    /// reads `n` rows.
+    pub fn prepare_load(&self, schema: &str, table: &str) -> Result<PreparedLoad> {
         let table_desc = self.state.descriptor.get_table_from_params(schema, table)?;
         let query_text = self
             .state
             .load_statements
             .get(&build_table_ident(schema, table))
             .expect("If we have a valid table, we should have an insert statement for it.");
-        let mut statement = self.transaction.prepare_cached(query_text)?;
+        Ok(PreparedLoad {
+            table: table_desc,
+            statement: self.transaction.prepare_cached(query_text)?,
+        })
+    }
+
+    /// Load the rows of a table for which a path into a JSON column has a given value, calling the callback with each.
+    ///
+    /// `path` is an sqlite JSON path, e.g. `$.a.b`, which is passed to `json_extract`.  `value` must serialize to a
+    /// string, number, or boolean: objects and arrays don't have a canonical text form to compare against, and null
+    /// never compares equal in SQL.  Failure behaves as with [Transaction::load_table].
+    pub fn load_table_json_filter<T: serde::de::DeserializeOwned, V: serde::Serialize>(
+        &self,
+        schema: &str,
+        table: &str,
+        json_column: &str,
+        path: &str,
+        value: V,
+        mut callback: impl FnMut(T) -> Result<()>,
+    ) -> Result<()> {
+        let table_desc = self.state.descriptor.get_table_from_params(schema, table)?;
+        let column = table_desc
+            .iter_columns()
+            .find(|c| c.get_name() == json_column)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Table {}.{} has no column {}", schema, table, json_column)
+            })?;
+        if column.get_column_type() != &crate::ColumnType::Json {
+            anyhow::bail!("{}.{}.{} isn't a JSON column", schema, table, json_column);
+        }
+        if !path.starts_with('$') {
+            anyhow::bail!("JSON paths must start with $, but got {}", path);
+        }
 
-        let mut rows = statement.query([])?;
-        while let Some(r) = rows.next()? {
-            let rv = crate::row_value::RowValue::from_rusqlite_row(table_desc, r)?;
-            callback(rv.deserialize()?)?;
+        let sql_value =
+            match serde_json::to_value(value)? {
+                serde_json::Value::Bool(b) => rusqlite::types::Value::Integer(b as i64),
+                serde_json::Value::String(s) => rusqlite::types::Value::Text(s),
+                serde_json::Value::Number(n) => {
+                    if let Some(i) = n.as_i64() {
+                        rusqlite::types::Value::Integer(i)
+                    } else {
+                        rusqlite::types::Value::Real(n.as_f64().ok_or_else(|| {
+                            anyhow::anyhow!("{} can't be represented in sqlite", n)
+                        })?)
+                    }
+                }
+                v => anyhow::bail!("Can't filter JSON columns on {}", v),
+            };
+
+        // The column name was validated when the table was declared, so it's safe to put it in the query.
+        let query_text = format!(
+            "{} WHERE json_extract({}, :path) = :value",
+            self.state
+                .load_statements
+                .get(&build_table_ident(schema, table))
+                .expect("If we have a valid table, we should have a load statement for it."),
+            json_column
+        );
+        let mut load = PreparedLoad {
+            table: table_desc,
+            statement: self.transaction.prepare_cached(&query_text)?,
+        };
+        for row in load.query(rusqlite::named_params! { ":path": path, ":value": sql_value })? {
+            callback(row?)?;
         }
 
         Ok(())
     }
 
+    /// Check whether a row with the given primary key exists, without loading it.
+    ///
+    /// For tables with one primary key column the key is a scalar.  For tables with more than one, it is a sequence
+    /// (e.g. a tuple) with one element per primary key column, in declaration order.  Errors if the table has no
+    /// primary key.
+    pub fn row_exists<K: serde::Serialize>(
+        &self,
+        schema: &str,
+        table: &str,
+        key: K,
+    ) -> Result<bool> {
+        let table_desc = self.state.descriptor.get_table_from_params(schema, table)?;
+        let query_text = self
+            .state
+            .exists_statements
+            .get(&build_table_ident(schema, table))
+            .ok_or_else(|| anyhow::anyhow!("Table {}.{} has no primary key", schema, table))?;
+        let mut statement = self.transaction.prepare_cached(query_text)?;
+
+        let rv = crate::row_value::RowValue::new_primary_key(table_desc, &key)?;
+        rv.bind_params(table_desc, &mut statement)?;
+        let mut rows = statement.raw_query();
+        Ok(rows.next()?.is_some())
+    }
+
     /// Patch a table.  This means:
     ///
     /// - For any table without a primary key, just insert the rows; or
@@ -331,11 +1100,51 @@ impl<'a> Transaction<'a> {
         Ok(())
     }
 
+    /// Scope this transaction to one schema, erroring if the schema doesn't exist.
+    pub fn schema(&mut self, name: &str) -> Result<SchemaTransaction<'_, 'a>> {
+        let state: &'a DatabaseState = self.state;
+        let schema = state
+            .descriptor
+            .iter_schemas()
+            .find(|s| s.get_name() == name)
+            .ok_or_else(|| anyhow::anyhow!("Schema {} not found", name))?;
+        Ok(SchemaTransaction {
+            transaction: self,
+            schema: schema.get_name(),
+        })
+    }
+
     pub fn commit(self) -> Result<()> {
         Ok(self.transaction.commit()?)
     }
 }
 
+impl<'t, 'a> SchemaTransaction<'t, 'a> {
+    pub fn get_schema_name(&self) -> &str {
+        self.schema
+    }
+
+    pub fn load_table<T: serde::de::DeserializeOwned>(
+        &self,
+        table: &str,
+        callback: impl FnMut(T) -> Result<()>,
+    ) -> Result<()> {
+        self.transaction.load_table(self.schema, table, callback)
+    }
+
+    pub fn patch_table<T: serde::Serialize>(&mut self, table: &str, values: &[T]) -> Result<()> {
+        self.transaction.patch_table(self.schema, table, values)
+    }
+
+    pub fn row_exists<K: serde::Serialize>(&self, table: &str, key: K) -> Result<bool> {
+        self.transaction.row_exists(self.schema, table, key)
+    }
+
+    pub fn truncate_table(&self, table: &str) -> Result<()> {
+        self.transaction.truncate_table(self.schema, table)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -354,6 +1163,13 @@ mod tests {
 
     /// Build a simple test schema, with a couple tables (intentionally of the same name).
     fn build_test_descriptor(path: &std::path::Path) -> Result<DatabaseDescriptor> {
+        build_test_descriptor_builder(path)?.build()
+    }
+
+    /// Like [build_test_descriptor], but leaves the builder unbuilt so that tests may customize it further.
+    fn build_test_descriptor_builder(
+        path: &std::path::Path,
+    ) -> Result<crate::DatabaseDescriptorBuilder> {
         let mut desc_builder = crate::DatabaseDescriptorBuilder::new(path.to_path_buf());
 
         for schema in ["schema1", "schema2"] {
@@ -392,7 +1208,7 @@ mod tests {
             })?;
         }
 
-        desc_builder.build()
+        Ok(desc_builder)
     }
 
     #[test]
@@ -527,4 +1343,782 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_row_exists() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let mut db = Database::open(desc).expect("Database should open");
+
+        let mut transaction = db.transaction().unwrap();
+        transaction
+            .patch_table(
+                "schema1",
+                "t1",
+                &[TestRow {
+                    primary_key: 5,
+                    string_col: "foo".into(),
+                    f64_col: None,
+                    json: None,
+                }],
+            )
+            .expect("Should patch");
+        transaction.commit().unwrap();
+
+        let transaction = db.transaction().unwrap();
+        assert!(transaction.row_exists("schema1", "t1", 5i64).unwrap());
+        assert!(!transaction.row_exists("schema1", "t1", 6i64).unwrap());
+        // Same key, but a different table.
+        assert!(!transaction.row_exists("schema1", "t2", 5i64).unwrap());
+    }
+
+    #[test]
+    fn test_row_exists_keys() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder
+            .add_schema("keys".into(), |b| {
+                b.add_table("no_key".into(), |tb| {
+                    tb.add_integer_column("id".into(), false, false)?;
+                    Ok(())
+                })?;
+                b.add_table("pair".into(), |tb| {
+                    tb.add_integer_column("a".into(), true, false)?;
+                    tb.add_string_column("b".into(), true, false)?;
+                    Ok(())
+                })?;
+                b.add_sql_migration(
+                    "create".into(),
+                    r#"
+                    CREATE TABLE {{ no_key }} (id INTEGER NOT NULL);
+                    CREATE TABLE {{ pair }} (
+                        a INTEGER NOT NULL,
+                        b TEXT NOT NULL,
+                        PRIMARY KEY (a, b)
+                    );
+                "#
+                    .into(),
+                )?;
+                Ok(())
+            })
+            .unwrap();
+        let mut db = Database::open(builder.build().unwrap()).expect("Database should open");
+        db.with_raw_connection(|conn| {
+            conn.execute("INSERT INTO `keys.pair` (a, b) VALUES (1, 'x')", [])?;
+            Ok(())
+        })
+        .unwrap();
+
+        let transaction = db.transaction().unwrap();
+        assert!(transaction.row_exists("keys", "no_key", 1i64).is_err());
+
+        assert!(transaction.row_exists("keys", "pair", (1i64, "x")).unwrap());
+        assert!(!transaction.row_exists("keys", "pair", (1i64, "y")).unwrap());
+        assert!(!transaction.row_exists("keys", "pair", (2i64, "x")).unwrap());
+        // Composite keys need one value per primary key column.
+        assert!(transaction.row_exists("keys", "pair", 1i64).is_err());
+        assert!(transaction
+            .row_exists("keys", "pair", (1i64, "x", 2i64))
+            .is_err());
+    }
+
+    #[test]
+    fn test_migration_history() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let db = Database::open(desc).expect("Database should open");
+
+        let history = db.migration_history().unwrap();
+        // 2 schemas, each with 2 tables, each of which has 2 migrations.
+        assert_eq!(history.len(), 8);
+
+        for schema in ["schema1", "schema2"] {
+            let names = history
+                .iter()
+                .filter(|x| x.schema == schema)
+                .map(|x| x.name.as_str())
+                .collect::<Vec<_>>();
+            for name in ["m1.t1", "m2.t1", "m1.t2", "m2.t2"] {
+                assert!(names.contains(&name), "{}: {}", schema, name);
+            }
+        }
+
+        for record in history.iter() {
+            assert!(record.ran_at.is_some());
+            assert!(record.duration >= 0.0);
+            assert!(!record.sql.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_custom_pragmas() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_pragma_config(crate::PragmaConfig {
+            cache_size: -2000,
+            busy_timeout_ms: 50,
+            mmap_size: Some(1 << 20),
+            synchronous: Some(crate::SynchronousMode::Normal),
+        });
+        let desc = builder.build().unwrap();
+        let mut db = Database::open(desc).expect("Database should open");
+
+        let cache_size: i64 = db
+            .conn
+            .query_row("PRAGMA cache_size", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(cache_size, -2000);
+        let journal_mode: String = db
+            .conn
+            .query_row("PRAGMA journal_mode", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(journal_mode.to_lowercase(), "wal");
+
+        let rows = vec![TestRow {
+            primary_key: 1,
+            string_col: "foo".into(),
+            f64_col: Some(1.0),
+            json: None,
+        }];
+        let mut transaction = db.transaction().unwrap();
+        transaction.patch_table("schema1", "t1", &rows[..]).unwrap();
+        transaction.commit().unwrap();
+
+        let mut loaded = vec![];
+        db.transaction()
+            .unwrap()
+            .load_table("schema1", "t1", |x: TestRow| {
+                loaded.push(x);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(loaded, rows);
+    }
+
+    #[test]
+    fn test_open_readonly() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let rows = vec![TestRow {
+            primary_key: 1,
+            string_col: "foo".into(),
+            f64_col: None,
+            json: None,
+        }];
+
+        {
+            let mut db = Database::open(desc).expect("Database should open");
+            let mut transaction = db.transaction().unwrap();
+            transaction.patch_table("schema1", "t1", &rows[..]).unwrap();
+            transaction.commit().unwrap();
+        }
+
+        // Writes are rejected at compile time; see the doctests on ReadTransaction.
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let mut db = Database::open_readonly(desc).expect("Should open read-only");
+        let transaction = db.transaction().unwrap();
+        let mut loaded = vec![];
+        transaction
+            .load_table("schema1", "t1", |x: TestRow| {
+                loaded.push(x);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(loaded, rows);
+        assert!(transaction.row_exists("schema1", "t1", 1i64).unwrap());
+
+        let mut out = vec![];
+        transaction
+            .export_table("schema1", "t1", ExportFormat::JsonLines, &mut out)
+            .unwrap();
+        let exported: TestRow =
+            serde_json::from_str(std::str::from_utf8(&out).unwrap().trim_end()).unwrap();
+        assert_eq!(exported, rows[0]);
+    }
+
+    #[test]
+    fn test_open_readonly_unmigrated() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        Database::open(desc).expect("Database should open");
+
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder
+            .add_schema("schema3".into(), |b| {
+                b.add_sql_migration("m1".into(), "".into())?;
+                Ok(())
+            })
+            .unwrap();
+        assert!(Database::open_readonly(builder.build().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_prepared_load() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let mut db = Database::open(desc).expect("Database should open");
+
+        let rows = (0i64..10)
+            .map(|x| TestRow {
+                primary_key: x,
+                string_col: format!("row{}", x),
+                f64_col: None,
+                json: None,
+            })
+            .collect::<Vec<_>>();
+        let mut transaction = db.transaction().unwrap();
+        transaction.patch_table("schema1", "t1", &rows[..]).unwrap();
+        transaction.commit().unwrap();
+
+        // Break the fourth row, so that reading it fails.
+        db.with_raw_connection(|conn| {
+            conn.execute(
+                "UPDATE `schema1.t1` SET json = 'not json' WHERE primary_key = 3",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+        let transaction = db.transaction().unwrap();
+        let mut load = transaction.prepare_load("schema1", "t1").unwrap();
+        // Only the first 3 rows are read, so the broken one doesn't matter.
+        let first = load
+            .iter::<TestRow>()
+            .unwrap()
+            .take(3)
+            .collect::<Result<Vec<_>>>()
+            .unwrap();
+        assert_eq!(first, rows[..3]);
+
+        // Running it again starts over, and reading everything hits the broken row.
+        let mut all = load.iter::<TestRow>().unwrap();
+        for r in rows[..3].iter() {
+            assert_eq!(&all.next().unwrap().unwrap(), r);
+        }
+        assert!(all.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn test_json_filter() {
+        /// Like [TestRow], but with a JSON column that can nest.
+        #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct JsonRow {
+            primary_key: i64,
+            string_col: String,
+            f64_col: Option<f64>,
+            json: serde_json::Value,
+        }
+
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let mut db = Database::open(desc).expect("Database should open");
+
+        let rows = (0i64..10)
+            .map(|x| JsonRow {
+                primary_key: x,
+                string_col: format!("row{}", x),
+                f64_col: None,
+                json: serde_json::json!({
+                    "parity": if x % 2 == 0 { "even" } else { "odd" },
+                    "stats": { "level": x / 3 },
+                }),
+            })
+            .collect::<Vec<_>>();
+        let mut transaction = db.transaction().unwrap();
+        transaction.patch_table("schema1", "t1", &rows[..]).unwrap();
+        transaction.commit().unwrap();
+
+        let transaction = db.transaction().unwrap();
+        let mut got = vec![];
+        transaction
+            .load_table_json_filter("schema1", "t1", "json", "$.stats.level", 1, |x: JsonRow| {
+                got.push(x);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(got, rows[3..6]);
+
+        let mut got = vec![];
+        transaction
+            .load_table_json_filter("schema1", "t1", "json", "$.parity", "odd", |x: JsonRow| {
+                got.push(x.primary_key);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(got, vec![1, 3, 5, 7, 9]);
+
+        // Only JSON columns can be filtered.
+        assert!(transaction
+            .load_table_json_filter("schema1", "t1", "string_col", "$", "foo", |_: JsonRow| {
+                Ok(())
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_raw_connection() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let mut db = Database::open(desc).expect("Database should open");
+
+        let rows = (1i64..=4)
+            .map(|x| TestRow {
+                primary_key: x,
+                string_col: format!("row{}", x),
+                f64_col: Some(x as f64),
+                json: None,
+            })
+            .collect::<Vec<_>>();
+        let mut transaction = db.transaction().unwrap();
+        transaction.patch_table("schema1", "t1", &rows[..]).unwrap();
+        transaction.commit().unwrap();
+
+        let (count, sum): (i64, i64) = db
+            .with_raw_connection(|conn| {
+                Ok(conn.query_row(
+                    "SELECT COUNT(*), SUM(primary_key) FROM `schema1.t1`",
+                    [],
+                    |r| Ok((r.get(0)?, r.get(1)?)),
+                )?)
+            })
+            .unwrap();
+        assert_eq!(count, 4);
+        assert_eq!(sum, 10);
+    }
+
+    #[test]
+    fn test_timestamp_column() {
+        #[derive(serde::Serialize)]
+        struct NewEvent {
+            id: i64,
+            message: String,
+        }
+
+        #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+        struct Event {
+            id: i64,
+            message: String,
+            created_at: f64,
+        }
+
+        let tdir = tempfile::TempDir::new().unwrap();
+        let mut desc_builder = crate::DatabaseDescriptorBuilder::new(tdir.path().to_path_buf());
+        desc_builder
+            .add_schema("logs".into(), |b| {
+                b.add_table("events".into(), |tb| {
+                    tb.add_integer_column("id".into(), true, false)?;
+                    tb.add_string_column("message".into(), false, false)?;
+                    tb.add_timestamp_column("created_at".into())?;
+                    Ok(())
+                })?;
+                b.add_sql_migration(
+                    "create".into(),
+                    r#"
+                    CREATE TABLE {{ events }} (
+                        id INTEGER PRIMARY KEY,
+                        message TEXT NOT NULL,
+                        created_at REAL NOT NULL
+                    );
+                "#
+                    .into(),
+                )?;
+                Ok(())
+            })
+            .unwrap();
+        let mut db = Database::open(desc_builder.build().unwrap()).unwrap();
+
+        let now = || {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs_f64()
+        };
+        let load = |transaction: &Transaction| {
+            let mut loaded: Vec<Event> = vec![];
+            transaction
+                .load_table("logs", "events", |e: Event| {
+                    loaded.push(e);
+                    Ok(())
+                })
+                .unwrap();
+            loaded
+        };
+
+        // sqlite's clock only has millisecond precision, so give it some slack.
+        let before = now() - 1.0;
+        let mut transaction = db.transaction().unwrap();
+        transaction
+            .patch_table(
+                "logs",
+                "events",
+                &[NewEvent {
+                    id: 1,
+                    message: "hello".into(),
+                }],
+            )
+            .unwrap();
+        let loaded = load(&transaction);
+        let after = now() + 1.0;
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].id, 1);
+        assert_eq!(loaded[0].message, "hello");
+        let created_at = loaded[0].created_at;
+        assert!(before <= created_at && created_at <= after);
+
+        // Patching the same key again without the column keeps the original timestamp.
+        transaction
+            .patch_table(
+                "logs",
+                "events",
+                &[NewEvent {
+                    id: 1,
+                    message: "goodbye".into(),
+                }],
+            )
+            .unwrap();
+        assert_eq!(
+            load(&transaction),
+            vec![Event {
+                id: 1,
+                message: "goodbye".into(),
+                created_at,
+            }]
+        );
+
+        // But an explicit value overwrites it.
+        let explicit = Event {
+            id: 1,
+            message: "explicit".into(),
+            created_at: 5.0,
+        };
+        transaction
+            .patch_table("logs", "events", std::slice::from_ref(&explicit))
+            .unwrap();
+        assert_eq!(load(&transaction), vec![explicit]);
+        transaction.commit().unwrap();
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let tdir = tempfile::TempDir::new().unwrap();
+
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_schema_version(2);
+        let db = Database::open(builder.build().unwrap()).expect("Fresh database should open");
+        assert_eq!(read_schema_version(&db.conn).unwrap(), 2);
+        std::mem::drop(db);
+
+        // The same version opens, both read-write and read-only.
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_schema_version(2);
+        Database::open(builder.build().unwrap()).expect("Matching version should open");
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_schema_version(2);
+        Database::open_readonly(builder.build().unwrap()).expect("Matching version should open");
+
+        // An older binary refuses.
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_schema_version(1);
+        assert!(Database::open(builder.build().unwrap()).is_err());
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_schema_version(1);
+        assert!(Database::open_readonly(builder.build().unwrap()).is_err());
+
+        // A newer one can't open read-only, but upgrades when opened read-write.
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_schema_version(3);
+        assert!(Database::open_readonly(builder.build().unwrap()).is_err());
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_schema_version(3);
+        let db = Database::open(builder.build().unwrap()).expect("Newer version should upgrade");
+        assert_eq!(read_schema_version(&db.conn).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_schema_version_commits_with_migrations() {
+        let tdir = tempfile::TempDir::new().unwrap();
+
+        // A failing migration must not leave the new version behind.
+        let mut builder = build_test_descriptor_builder(tdir.path()).unwrap();
+        builder.set_schema_version(1);
+        builder
+            .add_schema("broken".into(), |b| {
+                b.add_sql_migration("bad".into(), "THIS ISN'T SQL".into())
+            })
+            .unwrap();
+        assert!(Database::open(builder.build().unwrap()).is_err());
+
+        let db = Database::open(build_test_descriptor(tdir.path()).unwrap())
+            .expect("The old version should still open");
+        assert_eq!(read_schema_version(&db.conn).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_load_all_tables() {
+        struct Collector(Vec<(String, String, TestRow)>);
+
+        impl TableVisitor for Collector {
+            fn visit_row(
+                &mut self,
+                schema: &str,
+                table: &str,
+                row: crate::row_value::RowValue,
+            ) -> Result<()> {
+                self.0
+                    .push((schema.to_string(), table.to_string(), row.deserialize()?));
+                Ok(())
+            }
+        }
+
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let mut db = Database::open(desc).expect("Database should open");
+        let mut transaction = db.transaction().unwrap();
+
+        let mut expected = vec![];
+        for (i, (schema, table)) in [
+            ("schema1", "t1"),
+            ("schema1", "t2"),
+            ("schema2", "t1"),
+            ("schema2", "t2"),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let row = TestRow {
+                primary_key: i as i64,
+                string_col: format!("{}.{}", schema, table),
+                f64_col: None,
+                json: None,
+            };
+            transaction
+                .patch_table(schema, table, &[row.clone()])
+                .unwrap();
+            expected.push((schema.to_string(), table.to_string(), row));
+        }
+
+        let mut collector = Collector(vec![]);
+        transaction.load_all_tables(&mut collector).unwrap();
+        collector.0.sort_by_key(|x| x.2.primary_key);
+        assert_eq!(collector.0, expected);
+    }
+
+    #[test]
+    fn test_schema_transaction() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let mut db = Database::open(desc).expect("Database should open");
+
+        let rows = (0i64..5)
+            .map(|x| TestRow {
+                primary_key: x,
+                string_col: format!("row{}", x),
+                f64_col: Some(x as f64),
+                json: None,
+            })
+            .collect::<Vec<_>>();
+
+        let mut transaction = db.transaction().unwrap();
+        assert!(transaction.schema("missing").is_err());
+
+        transaction.patch_table("schema1", "t1", &rows[..]).unwrap();
+        {
+            let mut scoped = transaction.schema("schema2").unwrap();
+            assert_eq!(scoped.get_schema_name(), "schema2");
+            scoped.patch_table("t1", &rows[..]).unwrap();
+            assert!(scoped.row_exists("t1", 3).unwrap());
+            assert!(!scoped.row_exists("t2", 3).unwrap());
+        }
+
+        let mut unscoped: Vec<TestRow> = vec![];
+        transaction
+            .load_table("schema2", "t1", |r: TestRow| {
+                unscoped.push(r);
+                Ok(())
+            })
+            .unwrap();
+        let mut scoped: Vec<TestRow> = vec![];
+        transaction
+            .schema("schema1")
+            .unwrap()
+            .load_table("t1", |r: TestRow| {
+                scoped.push(r);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(scoped, rows);
+        assert_eq!(unscoped, rows);
+
+        transaction
+            .schema("schema2")
+            .unwrap()
+            .truncate_table("t1")
+            .unwrap();
+        assert!(!transaction.row_exists("schema2", "t1", 3).unwrap());
+    }
+
+    #[test]
+    fn test_export_table() {
+        /// Split one CSV record, handling quoting but not embedded newlines.
+        fn split_csv(line: &str) -> Vec<String> {
+            let mut fields = vec![String::new()];
+            let mut quoted = false;
+            let mut chars = line.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c {
+                    '"' if quoted && chars.peek() == Some(&'"') => {
+                        chars.next();
+                        fields.last_mut().unwrap().push('"');
+                    }
+                    '"' => quoted = !quoted,
+                    ',' if !quoted => fields.push(String::new()),
+                    c => fields.last_mut().unwrap().push(c),
+                }
+            }
+            fields
+        }
+
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let mut db = Database::open(desc).expect("Database should open");
+
+        let rows = (0i64..5)
+            .map(|x| TestRow {
+                primary_key: x,
+                string_col: format!("row \"{}\", with a comma", x),
+                f64_col: if x % 2 == 0 {
+                    Some(x as f64 + 0.5)
+                } else {
+                    None
+                },
+                json: Some([("key".to_string(), format!("value{}", x))].into()),
+            })
+            .collect::<Vec<_>>();
+        let mut transaction = db.transaction().unwrap();
+        transaction.patch_table("schema1", "t1", &rows[..]).unwrap();
+
+        let mut out = vec![];
+        transaction
+            .export_table("schema1", "t1", ExportFormat::JsonLines, &mut out)
+            .unwrap();
+        let parsed = std::str::from_utf8(&out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect::<Vec<TestRow>>();
+        assert_eq!(parsed, rows);
+
+        let mut out = vec![];
+        transaction
+            .export_table("schema1", "t1", ExportFormat::Csv, &mut out)
+            .unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let mut lines = text.split_terminator("\r\n");
+        assert_eq!(
+            split_csv(lines.next().unwrap()),
+            vec!["primary_key", "string_col", "f64_col", "json"]
+        );
+        let records = lines.map(split_csv).collect::<Vec<_>>();
+        assert_eq!(records.len(), rows.len());
+        for (r, expected) in records.iter().zip(rows.iter()) {
+            assert_eq!(r[0], expected.primary_key.to_string());
+            assert_eq!(r[1], expected.string_col);
+            assert_eq!(
+                r[2],
+                expected.f64_col.map(|x| x.to_string()).unwrap_or_default()
+            );
+            assert_eq!(
+                serde_json::from_str::<HashMap<String, String>>(&r[3]).unwrap(),
+                *expected.json.as_ref().unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_schema_drift() {
+        let tdir = tempfile::TempDir::new().unwrap();
+        let desc = build_test_descriptor(tdir.path()).unwrap();
+        let db = Database::open(desc).expect("Database should open");
+        assert_eq!(db.check_schema_drift().unwrap(), vec![]);
+        std::mem::drop(db);
+
+        let mut desc_builder = crate::DatabaseDescriptorBuilder::new(tdir.path().to_path_buf());
+        desc_builder
+            .add_schema("drift".into(), |b| {
+                b.add_table("t".into(), |tb| {
+                    tb.add_integer_column("id".into(), true, false)?;
+                    tb.add_string_column("wrong_type".into(), false, false)?;
+                    tb.add_f64_column("wrong_nullability".into(), false, false)?;
+                    tb.add_json_column("not_in_db".into())?;
+                    Ok(())
+                })?;
+                b.add_table("never_created".into(), |tb| {
+                    tb.add_integer_column("id".into(), true, false)?;
+                    Ok(())
+                })?;
+                b.add_table("text_pk".into(), |tb| {
+                    tb.add_string_column("id".into(), true, false)?;
+                    Ok(())
+                })?;
+                b.add_sql_migration(
+                    "create".into(),
+                    r#"
+                    CREATE TABLE {{ t }} (
+                        id INTEGER PRIMARY KEY,
+                        wrong_type REAL NOT NULL,
+                        wrong_nullability REAL,
+                        not_in_descriptor TEXT
+                    );
+                    CREATE TABLE {{ text_pk }} (id TEXT PRIMARY KEY);
+                "#
+                    .into(),
+                )?;
+                Ok(())
+            })
+            .unwrap();
+        let db = Database::open(desc_builder.build().unwrap()).expect("Database should open");
+
+        let schema = String::from("drift");
+        let table = String::from("t");
+        assert_eq!(
+            db.check_schema_drift().unwrap(),
+            vec![
+                DriftIssue::MissingTable {
+                    schema: schema.clone(),
+                    table: "never_created".into(),
+                },
+                DriftIssue::TypeMismatch {
+                    schema: schema.clone(),
+                    table: table.clone(),
+                    column: "wrong_type".into(),
+                    expected: crate::ColumnType::String,
+                    actual: "REAL".into(),
+                },
+                DriftIssue::NullabilityMismatch {
+                    schema: schema.clone(),
+                    table: table.clone(),
+                    column: "wrong_nullability".into(),
+                    descriptor_nullable: false,
+                },
+                DriftIssue::MissingColumn {
+                    schema: schema.clone(),
+                    table: table.clone(),
+                    column: "not_in_db".into(),
+                },
+                DriftIssue::ExtraColumn {
+                    schema: schema.clone(),
+                    table,
+                    column: "not_in_descriptor".into(),
+                },
+                // Unlike the rowid, a text primary key can be null.
+                DriftIssue::NullabilityMismatch {
+                    schema,
+                    table: "text_pk".into(),
+                    column: "id".into(),
+                    descriptor_nullable: false,
+                },
+            ]
+        );
+    }
 }
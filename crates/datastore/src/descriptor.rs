@@ -14,7 +14,7 @@ use std::collections::HashMap;
 use anyhow::Result;
 
 /// Types of a row's columns.
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd, Hash)]
+#[derive(Copy, Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Hash)]
 pub enum ColumnType {
     /// This column is a 64-bit signed integer.
     I64,
@@ -26,6 +26,13 @@ pub enum ColumnType {
     F64,
 }
 
+/// How to fill a column when the value being written doesn't provide one.
+#[derive(Debug, Eq, PartialEq)]
+pub enum ColumnDefault {
+    /// The current time, as f64 seconds since the unix epoch.
+    CurrentTimestamp,
+}
+
 /// A column in a table.
 #[derive(Debug)]
 pub struct ColumnDescriptor {
@@ -33,6 +40,7 @@ pub struct ColumnDescriptor {
     column_type: ColumnType,
     primary_key: bool,
     nullable: bool,
+    default: Option<ColumnDefault>,
 }
 
 /// Description of a table in a schema.
@@ -59,10 +67,37 @@ pub struct SchemaDescriptor {
     migrations: Vec<MigrationDescriptor>,
 }
 
+/// Values for sqlite's `synchronous` pragma.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SynchronousMode {
+    Off,
+    Normal,
+    Full,
+    Extra,
+}
+
+/// Tunable sqlite pragmas, applied whenever a connection is opened.
+///
+/// WAL and foreign key enforcement aren't configurable: the rest of this crate assumes them.  The defaults are tuned
+/// for a server with a reasonable amount of memory.
+#[derive(Clone, Debug)]
+pub struct PragmaConfig {
+    /// Passed to `PRAGMA cache_size`.  Negative values are in KiB, positive values are in pages.
+    pub cache_size: i64,
+    /// How long to wait on a locked database, in milliseconds.
+    pub busy_timeout_ms: u32,
+    /// If set, `PRAGMA mmap_size` in bytes.  Otherwise we leave sqlite's default alone.
+    pub mmap_size: Option<u64>,
+    /// If set, the `synchronous` pragma.  Otherwise we leave sqlite's default alone.
+    pub synchronous: Option<SynchronousMode>,
+}
+
 #[derive(Debug)]
 pub struct DatabaseDescriptor {
     path: std::path::PathBuf,
     schemas: HashMap<String, SchemaDescriptor>,
+    pragma_config: PragmaConfig,
+    schema_version: u32,
 }
 
 lazy_static::lazy_static! {
@@ -71,6 +106,30 @@ lazy_static::lazy_static! {
     };
 }
 
+impl SynchronousMode {
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            SynchronousMode::Off => "OFF",
+            SynchronousMode::Normal => "NORMAL",
+            SynchronousMode::Full => "FULL",
+            SynchronousMode::Extra => "EXTRA",
+        }
+    }
+}
+
+impl Default for PragmaConfig {
+    fn default() -> Self {
+        PragmaConfig {
+            // The default sqlite sets up is only a couple megabytes since they have to make their defaults work with
+            // e.g. phones.
+            cache_size: -100000,
+            busy_timeout_ms: 1000,
+            mmap_size: None,
+            synchronous: None,
+        }
+    }
+}
+
 impl ColumnDescriptor {
     fn new(
         name: String,
@@ -104,6 +163,7 @@ impl ColumnDescriptor {
             column_type,
             primary_key,
             nullable,
+            default: None,
         })
     }
 
@@ -122,6 +182,11 @@ impl ColumnDescriptor {
     pub fn is_nullable(&self) -> bool {
         self.nullable
     }
+
+    /// The value used if a row being written omits this column, if any.
+    pub fn get_default(&self) -> Option<&ColumnDefault> {
+        self.default.as_ref()
+    }
 }
 
 impl TableDescriptor {
@@ -190,8 +255,18 @@ impl SchemaDescriptor {
 }
 
 impl DatabaseDescriptor {
-    fn new(path: std::path::PathBuf, schemas: HashMap<String, SchemaDescriptor>) -> Result<Self> {
-        Ok(DatabaseDescriptor { path, schemas })
+    fn new(
+        path: std::path::PathBuf,
+        schemas: HashMap<String, SchemaDescriptor>,
+        pragma_config: PragmaConfig,
+        schema_version: u32,
+    ) -> Result<Self> {
+        Ok(DatabaseDescriptor {
+            path,
+            schemas,
+            pragma_config,
+            schema_version,
+        })
     }
 
     pub fn iter_schemas(&self) -> impl Iterator<Item = &SchemaDescriptor> {
@@ -202,6 +277,14 @@ impl DatabaseDescriptor {
         self.path.as_path()
     }
 
+    pub fn get_pragma_config(&self) -> &PragmaConfig {
+        &self.pragma_config
+    }
+
+    pub fn get_schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
     /// get a reference to a  given table in the database from the schema/name pair, returning an error if this wasn't possible.
 
     pub fn get_table_from_params(&self, schema: &str, table: &str) -> Result<&TableDescriptor> {
@@ -293,6 +376,19 @@ impl TableDescriptorBuilder {
         Ok(())
     }
 
+    /// Add an f64 column holding unix seconds, which is filled with the current time if the row being written omits it
+    /// or sets it to null.
+    ///
+    /// This is a created-at timestamp: when a patch replaces an existing row and omits the column, the existing row's
+    /// value is kept.  Rows which provide a value always overwrite it.
+    pub fn add_timestamp_column(&mut self, name: String) -> Result<()> {
+        self.check_name(&name)?;
+        let mut column = ColumnDescriptor::new(name, ColumnType::F64, false, false)?;
+        column.default = Some(ColumnDefault::CurrentTimestamp);
+        self.columns.push(column);
+        Ok(())
+    }
+
     pub fn build(self) -> Result<TableDescriptor> {
         TableDescriptor::new(self.name, self.columns)
     }
@@ -360,6 +456,8 @@ impl SchemaDescriptorBuilder {
 pub struct DatabaseDescriptorBuilder {
     path: std::path::PathBuf,
     schemas: HashMap<String, SchemaDescriptor>,
+    pragma_config: PragmaConfig,
+    schema_version: u32,
 }
 
 impl DatabaseDescriptorBuilder {
@@ -367,9 +465,24 @@ impl DatabaseDescriptorBuilder {
         DatabaseDescriptorBuilder {
             path,
             schemas: Default::default(),
+            pragma_config: Default::default(),
+            schema_version: 0,
         }
     }
 
+    /// Set the application's schema version.  If never called, the version is 0.
+    ///
+    /// Opening a database which was last written with a higher version is an error, since this binary won't know what
+    /// changed.  Opening one with a lower version runs the migrations and then records the new version.
+    pub fn set_schema_version(&mut self, version: u32) {
+        self.schema_version = version;
+    }
+
+    /// Replace the pragmas applied when opening the database.  If never called, [PragmaConfig::default] is used.
+    pub fn set_pragma_config(&mut self, config: PragmaConfig) {
+        self.pragma_config = config;
+    }
+
     /// Add a schema.  Takes a closure which will be passed a reference to a builder, which we build for you once the
     /// closure returns.
     pub fn add_schema<F>(&mut self, name: String, schema_builder: F) -> Result<()>
@@ -393,7 +506,12 @@ impl DatabaseDescriptorBuilder {
     }
 
     pub fn build(self) -> Result<DatabaseDescriptor> {
-        DatabaseDescriptor::new(self.path, self.schemas)
+        DatabaseDescriptor::new(
+            self.path,
+            self.schemas,
+            self.pragma_config,
+            self.schema_version,
+        )
     }
 }
 
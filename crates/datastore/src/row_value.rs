@@ -4,7 +4,7 @@
 use anyhow::Result;
 use smallvec::SmallVec;
 
-use crate::descriptor::{ColumnType, TableDescriptor};
+use crate::descriptor::{ColumnDescriptor, ColumnType, TableDescriptor};
 
 #[derive(Debug)]
 enum ColumnValue {
@@ -30,10 +30,54 @@ struct RowMap {
 }
 
 #[derive(Debug)]
-pub(crate) struct RowValue {
+pub struct RowValue {
     map: RowMap,
 }
 
+/// Convert a JSON value to the value for a given column, validating the type and nullability.
+///
+/// Takes the value mutably so that JSON columns can avoid a clone.
+fn column_value_from_json(
+    column: &ColumnDescriptor,
+    v: &mut serde_json::Value,
+) -> Result<ColumnValue> {
+    if v.is_null() && column.get_column_type() != &ColumnType::Json {
+        if !column.is_nullable() {
+            anyhow::bail!(
+                "{}: got null value but column isn't nullable",
+                column.get_name()
+            );
+        }
+        return Ok(ColumnValue::Null);
+    }
+
+    Ok(match column.get_column_type() {
+        ColumnType::I64 => ColumnValue::I64(v.as_i64().ok_or_else(|| {
+            anyhow::anyhow!("{}: integer isn't representable as i64", column.get_name())
+        })?),
+        ColumnType::String => ColumnValue::String(
+            v.as_str()
+                .ok_or_else(|| anyhow::anyhow!("{}: should be a string", column.get_name()))?
+                .to_string(),
+        ),
+        ColumnType::Json => ColumnValue::Json(v.take()),
+        ColumnType::F64 => ColumnValue::F64(
+            v.as_f64()
+                .ok_or_else(|| anyhow::anyhow!("{}: isn't an f64", column.get_name()))?,
+        ),
+    })
+}
+
+/// Write a CSV field, quoting it if it contains anything that would otherwise be ambiguous.
+pub(crate) fn write_csv_field(out: &mut dyn std::io::Write, field: &str) -> Result<()> {
+    if field.contains(&[',', '"', '\r', '\n'][..]) {
+        write!(out, "\"{}\"", field.replace('"', "\"\""))?;
+    } else {
+        out.write_all(field.as_bytes())?;
+    }
+    Ok(())
+}
+
 impl RowValue {
     /// Make a row for the specified table.
     pub fn new(descriptor: &TableDescriptor, value: &impl serde::Serialize) -> Result<RowValue> {
@@ -43,34 +87,17 @@ impl RowValue {
         let mut map: RowMap = Default::default();
 
         for i in descriptor.iter_columns() {
-            let v = json.get_mut(i.get_name()).ok_or_else(|| {
-                anyhow::anyhow!(
+            let v = json.get_mut(i.get_name());
+
+            // Columns with defaults are bound as null when omitted, and the insert statement fills them in.
+            let cval = match (v, i.get_default()) {
+                (None, Some(_)) => ColumnValue::Null,
+                (Some(v), Some(_)) if v.is_null() => ColumnValue::Null,
+                (Some(v), _) => column_value_from_json(i, v)?,
+                (None, None) => anyhow::bail!(
                     "Input struct doesn't have field for column {}",
                     i.get_name()
-                )
-            })?;
-
-            let cval = if v.is_null() && i.get_column_type() != &ColumnType::Json {
-                if !i.is_nullable() {
-                    anyhow::bail!("{}: got null value but column isn't nullable", i.get_name());
-                }
-                ColumnValue::Null
-            } else {
-                match i.get_column_type() {
-                    ColumnType::I64 => ColumnValue::I64(v.as_i64().ok_or_else(|| {
-                        anyhow::anyhow!("{}: integer isn't representable as i64", i.get_name())
-                    })?),
-                    ColumnType::String => ColumnValue::String(
-                        v.as_str()
-                            .ok_or_else(|| anyhow::anyhow!("{}: should be a string", i.get_name()))?
-                            .to_string(),
-                    ),
-                    ColumnType::Json => ColumnValue::Json(v.take()),
-                    ColumnType::F64 => ColumnValue::F64(
-                        v.as_f64()
-                            .ok_or_else(|| anyhow::anyhow!("{}: isn't an f64", i.get_name()))?,
-                    ),
-                }
+                ),
             };
 
             map.entries.push(RowMapEntry {
@@ -82,6 +109,47 @@ impl RowValue {
         Ok(RowValue { map })
     }
 
+    /// Build a row containing only the primary key columns of the specified table.
+    ///
+    /// If the table has one primary key column, the key should serialize to a scalar.  Otherwise, it should serialize
+    /// to a sequence (e.g. a tuple) with one element per primary key column, in the order the columns were declared.
+    pub fn new_primary_key(
+        descriptor: &TableDescriptor,
+        key: &impl serde::Serialize,
+    ) -> Result<RowValue> {
+        let pk_columns = descriptor
+            .iter_columns()
+            .filter(|x| x.is_primary_key())
+            .collect::<SmallVec<[&ColumnDescriptor; 4]>>();
+        if pk_columns.is_empty() {
+            anyhow::bail!("{}: table has no primary key", descriptor.get_name());
+        }
+
+        let json = serde_json::to_value(key)?;
+        let mut values = if pk_columns.len() == 1 {
+            vec![json]
+        } else {
+            match json {
+                serde_json::Value::Array(a) if a.len() == pk_columns.len() => a,
+                _ => anyhow::bail!(
+                    "{}: key should be a sequence of {} values",
+                    descriptor.get_name(),
+                    pk_columns.len()
+                ),
+            }
+        };
+
+        let mut map: RowMap = Default::default();
+        for (c, v) in pk_columns.iter().zip(values.iter_mut()) {
+            map.entries.push(RowMapEntry {
+                name: c.get_name().to_string(),
+                value: column_value_from_json(c, v)?,
+            });
+        }
+
+        Ok(RowValue { map })
+    }
+
     /// Build this row from a Rusqlite row and a given table.
     pub fn from_rusqlite_row(table: &crate::TableDescriptor, row: &rusqlite::Row) -> Result<Self> {
         use crate::ColumnType as CT;
@@ -117,6 +185,11 @@ impl RowValue {
     pub fn deserialize<T: serde::de::DeserializeOwned>(self) -> Result<T> {
         // We build a JSON value, then deserialize from that.  As with building row values, we can later opt to
         // implement deserializer directly if we need to.
+        Ok(serde_json::from_value(self.into_json())?)
+    }
+
+    /// Convert this row to a JSON object mapping column names to values.  JSON columns are embedded as-is.
+    pub fn into_json(self) -> serde_json::Value {
         let mut jval = serde_json::json!({});
 
         let map = jval
@@ -137,7 +210,32 @@ impl RowValue {
             map.insert(i.name, cval);
         }
 
-        Ok(serde_json::from_value(jval)?)
+        jval
+    }
+
+    /// Write this row as one CSV record, terminated by `\r\n`.
+    ///
+    /// Columns are written in the order of the table.  Null is an empty field, and JSON columns are written as their
+    /// serialized JSON text.
+    pub fn write_csv_record(&self, out: &mut dyn std::io::Write) -> Result<()> {
+        use ColumnValue::*;
+
+        for (i, e) in self.map.entries.iter().enumerate() {
+            if i != 0 {
+                out.write_all(b",")?;
+            }
+
+            match &e.value {
+                Null => {}
+                F64(x) => write!(out, "{}", x)?,
+                I64(x) => write!(out, "{}", x)?,
+                I128(x) => write!(out, "{}", x)?,
+                String(x) => write_csv_field(out, x)?,
+                Json(x) => write_csv_field(out, &serde_json::to_string(x)?)?,
+            }
+        }
+        out.write_all(b"\r\n")?;
+        Ok(())
     }
 
     /// Bind the parameters of this row to a statement.  The statement is expected to have named parameters for all
@@ -42,6 +42,16 @@ impl<T: Num + Copy + std::cmp::PartialOrd> Aabb<T> {
         let wh = p2 - p1;
         Ok(Aabb { p1, wh })
     }
+
+    /// The tightest box containing all of the given points, or `None` if there aren't any.
+    pub fn from_points_iter(mut points: impl Iterator<Item = V2<T>>) -> Option<Aabb<T>> {
+        let first = points.next()?;
+        let (lo, hi) = points.fold((first, first), |(lo, hi), p| (lo.min(p), hi.max(p)));
+        Some(Aabb {
+            p1: lo,
+            wh: hi - lo,
+        })
+    }
 }
 
 impl<T: Num + Copy> Aabb<T>
@@ -82,6 +92,29 @@ where
     }
 }
 
+impl Aabb<f64> {
+    /// Grow the box by `margin` on every side.  See [Aabb::inflate_xy].
+    #[must_use = "This returns a new box"]
+    pub fn inflate(&self, margin: f64) -> Aabb<f64> {
+        self.inflate_xy(margin, margin)
+    }
+
+    /// Grow the box by `mx` on the left and right and by `my` on the top and bottom.
+    ///
+    /// Negative margins shrink the box.  If the box would invert on an axis, it instead collapses to zero size at its
+    /// center on that axis.
+    #[must_use = "This returns a new box"]
+    pub fn inflate_xy(&self, mx: f64, my: f64) -> Aabb<f64> {
+        let center = self.get_center();
+        let half_width = (self.get_half_width() + mx).max(0.0);
+        let half_height = (self.get_half_height() + my).max(0.0);
+        Aabb {
+            p1: V2::new(center.x - half_width, center.y - half_height),
+            wh: V2::new(half_width * 2.0, half_height * 2.0),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -95,4 +128,59 @@ mod tests {
         approx::assert_relative_eq!(b.get_half_height(), 2.0);
         Ok(())
     }
+
+    #[test]
+    fn test_inflate() -> Result<()> {
+        let b = Aabb::from_points(V2::new(1.0, 1.0), V2::new(3.0, 5.0))?;
+        let inflated = b.inflate(0.5);
+        assert!(inflated.get_p1().x < b.get_p1().x && inflated.get_p1().y < b.get_p1().y);
+        assert!(inflated.get_p2().x > b.get_p2().x && inflated.get_p2().y > b.get_p2().y);
+        approx::assert_relative_eq!(inflated.get_width(), 3.0);
+        approx::assert_relative_eq!(inflated.get_height(), 5.0);
+
+        let inflated = b.inflate_xy(1.0, 0.0);
+        approx::assert_relative_eq!(inflated.get_width(), 4.0);
+        approx::assert_relative_eq!(inflated.get_height(), 4.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shrink() -> Result<()> {
+        let b = Aabb::from_points(V2::new(1.0, 1.0), V2::new(3.0, 5.0))?;
+        let shrunk = b.inflate(-0.5);
+        approx::assert_relative_eq!(shrunk.get_width(), 1.0);
+        approx::assert_relative_eq!(shrunk.get_height(), 3.0);
+
+        // Shrinking by more than the half width collapses x, but y is still fine.
+        let shrunk = b.inflate(-1.5);
+        approx::assert_relative_eq!(shrunk.get_width(), 0.0);
+        approx::assert_relative_eq!(shrunk.get_height(), 1.0);
+        approx::assert_relative_eq!(shrunk.get_p1().x, 2.0);
+        assert!(shrunk.get_p1().x <= shrunk.get_p2().x && shrunk.get_p1().y <= shrunk.get_p2().y);
+
+        let collapsed = b.inflate(-100.0);
+        approx::assert_relative_eq!(collapsed.get_width(), 0.0);
+        approx::assert_relative_eq!(collapsed.get_height(), 0.0);
+        assert_eq!(collapsed.get_center(), b.get_center());
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_points_iter() {
+        assert!(Aabb::<f64>::from_points_iter(std::iter::empty()).is_none());
+
+        let single = Aabb::from_points_iter(std::iter::once(V2::new(1.0, 2.0))).unwrap();
+        assert_eq!(single.get_p1(), V2::new(1.0, 2.0));
+        assert_eq!(single.get_p2(), V2::new(1.0, 2.0));
+
+        let points = [
+            V2::new(1.0, 5.0),
+            V2::new(-2.0, 3.0),
+            V2::new(4.0, -1.0),
+            V2::new(0.0, 0.0),
+        ];
+        let aabb = Aabb::from_points_iter(points.iter().copied()).unwrap();
+        assert_eq!(aabb.get_p1(), V2::new(-2.0, -1.0));
+        assert_eq!(aabb.get_p2(), V2::new(4.0, 5.0));
+    }
 }
@@ -3,7 +3,7 @@ use num::Num;
 
 use crate::*;
 
-pub(crate) fn aabb_aabb_test<T>(box1: &Aabb<T>, box2: &Aabb<T>) -> bool
+pub fn aabb_aabb_test<T>(box1: &Aabb<T>, box2: &Aabb<T>) -> bool
 where
     T: Num + Copy + std::cmp::PartialOrd,
 {
@@ -18,10 +18,38 @@ where
         && dist_y <= (box1.get_height() + box2.get_height())
 }
 
+/// Compute the contact between two boxes, or `None` if they don't overlap.
+///
+/// The normal is along the axis of least penetration, and the point is the center of the overlapping region.  Boxes
+/// which are exactly touching produce a contact with a depth of 0.
+pub fn aabb_aabb_contact(box1: &Aabb<f64>, box2: &Aabb<f64>) -> Option<Contact> {
+    let lo = box1.get_p1().max(box2.get_p1());
+    let hi = box1.get_p2().min(box2.get_p2());
+    let overlap = hi - lo;
+    if overlap.x < 0.0 || overlap.y < 0.0 {
+        return None;
+    }
+
+    let delta = box2.get_center() - box1.get_center();
+    let sign = |x: f64| if x < 0.0 { -1.0 } else { 1.0 };
+    let (normal, depth) = if overlap.x <= overlap.y {
+        (V2::new(sign(delta.x), 0.0), overlap.x)
+    } else {
+        (V2::new(0.0, sign(delta.y)), overlap.y)
+    };
+
+    Some(Contact {
+        normal,
+        depth,
+        point: (lo + hi) / 2.0,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use approx::assert_relative_eq;
     use proptest::prelude::*;
 
     // A second implementation of a box-box collision algorithm that we know to be correct.
@@ -43,6 +71,40 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn contact_normal_and_depth() -> anyhow::Result<()> {
+        let b1 = Aabb::from_points(V2::new(0.0, 0.0), V2::new(2.0, 2.0))?;
+        let b2 = Aabb::from_points(V2::new(1.5, 0.5), V2::new(3.5, 2.5))?;
+        let contact = aabb_aabb_contact(&b1, &b2).expect("Should overlap");
+        assert_eq!(contact.normal, V2::new(1.0, 0.0));
+        assert_relative_eq!(contact.depth, 0.5);
+        assert_relative_eq!(contact.point.x, 1.75);
+        assert_relative_eq!(contact.point.y, 1.25);
+
+        let contact = aabb_aabb_contact(&b2, &b1).expect("Should overlap");
+        assert_eq!(contact.normal, V2::new(-1.0, 0.0));
+        assert_relative_eq!(contact.depth, 0.5);
+
+        let b3 = Aabb::from_points(V2::new(0.5, -1.5), V2::new(1.5, 0.25))?;
+        let contact = aabb_aabb_contact(&b1, &b3).expect("Should overlap");
+        assert_eq!(contact.normal, V2::new(0.0, -1.0));
+        assert_relative_eq!(contact.depth, 0.25);
+        Ok(())
+    }
+
+    #[test]
+    fn contact_touching_and_separated() -> anyhow::Result<()> {
+        let b1 = Aabb::from_points(V2::new(0.0, 0.0), V2::new(1.0, 1.0))?;
+        let b2 = Aabb::from_points(V2::new(1.0, 0.0), V2::new(2.0, 1.0))?;
+        let contact = aabb_aabb_contact(&b1, &b2).expect("Touching boxes should have a contact");
+        assert_eq!(contact.normal, V2::new(1.0, 0.0));
+        assert_eq!(contact.depth, 0.0);
+
+        let b3 = Aabb::from_points(V2::new(1.1, 0.0), V2::new(2.0, 1.0))?;
+        assert!(aabb_aabb_contact(&b1, &b3).is_none());
+        Ok(())
+    }
+
     proptest! {
         #![proptest_config(ProptestConfig::with_cases(100000))]
         #[test]
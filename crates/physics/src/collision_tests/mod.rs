@@ -1 +1,18 @@
+use crate::*;
+
 pub(crate) mod aabb_aabb;
+pub(crate) mod swept_aabb_aabb;
+
+/// The contact between two overlapping shapes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Contact {
+    /// Unit normal pointing from the first shape toward the second.
+    pub normal: V2<f64>,
+    /// How far the shapes overlap along the normal.  Zero if they are only touching.
+    pub depth: f64,
+    /// A representative point of the contact, in world space.
+    pub point: V2<f64>,
+}
+
+pub use aabb_aabb::*;
+pub use swept_aabb_aabb::*;
@@ -0,0 +1,178 @@
+//! Swept AABB-AABB collision, for boxes which move far enough in one step to tunnel through each other.
+use crate::*;
+
+/// Entry and exit times along one axis, for a moving interval `[a_min, a_max]` and a static interval `[b_min, b_max]`.
+///
+/// Returns `None` if the intervals never overlap.
+fn axis_entry_exit(a_min: f64, a_max: f64, b_min: f64, b_max: f64, vel: f64) -> Option<(f64, f64)> {
+    if vel > 0.0 {
+        Some(((b_min - a_max) / vel, (b_max - a_min) / vel))
+    } else if vel < 0.0 {
+        Some(((b_max - a_min) / vel, (b_min - a_max) / vel))
+    } else if a_min <= b_max && b_min <= a_max {
+        // Not moving on this axis, but always overlapping on it.
+        Some((f64::NEG_INFINITY, f64::INFINITY))
+    } else {
+        None
+    }
+}
+
+/// Sweep `a` along `a_vel` for `dt` against the stationary `b`.
+///
+/// Returns the time of impact in `[0, dt]` and the unit normal of the face which was hit, pointing from `a` to `b` as
+/// with [Contact].  This is the same per-axis entry/exit computation as the slab test in the raycasting module, but on
+/// the Minkowski sum of the boxes.
+///
+/// Boxes which already touch or overlap at the start of the step hit at time 0 with the normal of [aabb_aabb_contact],
+/// but only if `a` is moving into `b` along that normal.  Moving apart or sliding along the surface, e.g. walking on
+/// the ground, isn't a hit.
+pub fn swept_aabb_aabb(
+    a: &Aabb<f64>,
+    a_vel: V2<f64>,
+    b: &Aabb<f64>,
+    dt: f64,
+) -> Option<(f64, V2<f64>)> {
+    let (x_entry, x_exit) = axis_entry_exit(
+        a.get_p1().x,
+        a.get_p2().x,
+        b.get_p1().x,
+        b.get_p2().x,
+        a_vel.x,
+    )?;
+    let (y_entry, y_exit) = axis_entry_exit(
+        a.get_p1().y,
+        a.get_p2().y,
+        b.get_p1().y,
+        b.get_p2().y,
+        a_vel.y,
+    )?;
+
+    let entry = x_entry.max(y_entry);
+    let exit = x_exit.min(y_exit);
+    if entry > exit || entry > dt || exit < 0.0 {
+        return None;
+    }
+
+    if entry < 0.0 {
+        return aabb_aabb_contact(a, b)
+            .filter(|c| a_vel.dot(&c.normal) > 0.0)
+            .map(|c| (0.0, c.normal));
+    }
+
+    let sign = |x: f64| if x < 0.0 { -1.0 } else { 1.0 };
+    let normal = if x_entry > y_entry {
+        V2::new(sign(a_vel.x), 0.0)
+    } else {
+        V2::new(0.0, sign(a_vel.y))
+    };
+    Some((entry, normal))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use approx::assert_relative_eq;
+    use proptest::prelude::*;
+
+    fn translate(b: &Aabb<f64>, by: V2<f64>) -> Aabb<f64> {
+        Aabb::from_points(b.get_p1() + by, b.get_p2() + by).unwrap()
+    }
+
+    #[test]
+    fn basic() -> anyhow::Result<()> {
+        let a = Aabb::from_points(V2::new(0.0, 0.0), V2::new(1.0, 1.0))?;
+        let wall = Aabb::from_points(V2::new(5.0, -10.0), V2::new(6.0, 10.0))?;
+
+        // Far enough in one step to pass entirely through the wall.
+        let (t, normal) = swept_aabb_aabb(&a, V2::new(20.0, 0.0), &wall, 1.0).expect("Should hit");
+        assert_relative_eq!(t, 0.2);
+        assert_eq!(normal, V2::new(1.0, 0.0));
+
+        // Stops short.
+        assert!(swept_aabb_aabb(&a, V2::new(20.0, 0.0), &wall, 0.1).is_none());
+        // Moving away.
+        assert!(swept_aabb_aabb(&a, V2::new(-20.0, 0.0), &wall, 1.0).is_none());
+
+        // Landing on a floor.
+        let floor = Aabb::from_points(V2::new(-10.0, -3.0), V2::new(10.0, -2.0))?;
+        let (t, normal) = swept_aabb_aabb(&a, V2::new(1.0, -4.0), &floor, 1.0).expect("Should hit");
+        assert_relative_eq!(t, 0.5);
+        assert_eq!(normal, V2::new(0.0, -1.0));
+        Ok(())
+    }
+
+    #[test]
+    fn starting_overlap() -> anyhow::Result<()> {
+        let a = Aabb::from_points(V2::new(0.0, 0.0), V2::new(2.0, 2.0))?;
+        let b = Aabb::from_points(V2::new(1.5, 0.0), V2::new(3.5, 2.0))?;
+        let (t, normal) = swept_aabb_aabb(&a, V2::new(1.0, 0.0), &b, 1.0).expect("Should hit");
+        assert_eq!(t, 0.0);
+        assert_eq!(normal, V2::new(1.0, 0.0));
+
+        // Not moving into the other box isn't a hit, even when overlapping.
+        assert!(swept_aabb_aabb(&a, V2::new(0.0, 0.0), &b, 1.0).is_none());
+        assert!(swept_aabb_aabb(&a, V2::new(-1.0, 0.0), &b, 1.0).is_none());
+
+        // Touching and moving apart isn't a hit.
+        let c = Aabb::from_points(V2::new(2.0, 0.0), V2::new(3.0, 2.0))?;
+        assert!(swept_aabb_aabb(&a, V2::new(-1.0, 0.0), &c, 1.0).is_none());
+
+        // Neither is sliding along a floor, but falling into it is.
+        let unit = Aabb::from_points(V2::new(0.0, 0.0), V2::new(1.0, 1.0))?;
+        let floor = Aabb::from_points(V2::new(-10.0, -1.0), V2::new(10.0, 0.0))?;
+        assert!(swept_aabb_aabb(&unit, V2::new(5.0, 0.0), &floor, 1.0).is_none());
+        let (t, normal) =
+            swept_aabb_aabb(&unit, V2::new(5.0, -1.0), &floor, 1.0).expect("Should hit");
+        assert_eq!(t, 0.0);
+        assert_eq!(normal, V2::new(0.0, -1.0));
+        Ok(())
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(10000))]
+        #[test]
+        fn fuzz_against_substepping(
+            ax in -50.0..=50.0f64,
+            ay in -50.0..=50.0f64,
+            aw in 0.1..=10.0f64,
+            ah in 0.1..=10.0f64,
+            bx in -50.0..=50.0f64,
+            by in -50.0..=50.0f64,
+            bw in 0.1..=10.0f64,
+            bh in 0.1..=10.0f64,
+            vx in -100.0..=100.0f64,
+            vy in -100.0..=100.0f64,
+        ) {
+            let a = Aabb::from_points(V2::new(ax, ay), V2::new(ax + aw, ay + ah)).unwrap();
+            let b = Aabb::from_points(V2::new(bx, by), V2::new(bx + bw, by + bh)).unwrap();
+            let vel = V2::new(vx, vy);
+            let swept = swept_aabb_aabb(&a, vel, &b, 1.0);
+
+            // Boxes which start out overlapping only hit if moving into each other.
+            if let Some(c) = aabb_aabb_contact(&a, &b) {
+                prop_assert_eq!(swept.is_some(), vel.dot(&c.normal) > 0.0, "{:?} {:?} {:?}", a, b, vel);
+            } else {
+                // Any overlap found by substepping must also be found by the sweep.  The converse doesn't hold, since
+                // substepping can step over grazing hits.
+                const SUBSTEPS: usize = 1000;
+                let substep_hit = (0..=SUBSTEPS)
+                    .map(|i| i as f64 / SUBSTEPS as f64)
+                    .any(|t| aabb_aabb_test(&translate(&a, vel * t), &b));
+                if substep_hit {
+                    prop_assert!(swept.is_some(), "{:?} {:?} {:?}", a, b, vel);
+                }
+            }
+
+            if let Some((t, normal)) = swept {
+                prop_assert!((0.0..=1.0).contains(&t));
+                // At the time of impact the boxes are touching, give or take rounding.
+                let moved = translate(&a, vel * t).inflate(1e-6);
+                prop_assert!(aabb_aabb_test(&moved, &b), "{:?} {:?} {:?} {}", a, b, vel, t);
+                prop_assert_eq!(normal.length(), 1.0);
+                // The hit is always on a face we were moving toward.
+                prop_assert!(normal.dot(&vel) > 0.0, "{:?} {:?}", normal, vel);
+            }
+        }
+    }
+}
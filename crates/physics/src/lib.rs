@@ -11,6 +11,7 @@ mod raycasting;
 mod v2;
 
 pub use aabb::*;
+pub use collision_tests::*;
 pub use morton_tree::*;
 pub use ray::*;
 pub use raycasting::*;
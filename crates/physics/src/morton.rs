@@ -51,6 +51,15 @@ fn collapse_u32(x: u32) -> u16 {
     res as u16
 }
 
+/// Add 32768 to an i16, producing the equivalent u16.  Flipping the sign bit is the same thing.
+const fn bias_i16(x: i16) -> u16 {
+    (x as u16) ^ 0x8000
+}
+
+const fn unbias_u16(x: u16) -> i16 {
+    (x ^ 0x8000) as i16
+}
+
 impl MortonCode {
     pub fn encode(x: u16, y: u16) -> MortonCode {
         MortonCode {
@@ -63,6 +72,20 @@ impl MortonCode {
         (collapse_u32(self.data), collapse_u32(self.data >> 1))
     }
 
+    /// Encode signed coordinates by biasing them by 32768, so that `(i16::MIN, i16::MIN)` maps to `(0, 0)`.
+    ///
+    /// Codes from this function order by the biased coordinates, and shouldn't be mixed with codes from
+    /// [MortonCode::encode] unless the caller is applying the same bias.
+    pub fn encode_signed(x: i16, y: i16) -> MortonCode {
+        MortonCode::encode(bias_i16(x), bias_i16(y))
+    }
+
+    /// Inverse of [MortonCode::encode_signed], returning `(x, y)`.
+    pub fn decode_signed(&self) -> (i16, i16) {
+        let (x, y) = self.decode();
+        (unbias_u16(x), unbias_u16(y))
+    }
+
     /// Expand this morton code into two-bit pairs.
     ///
     /// Each pair is `yx` where the high bit is set if the high bit would have been set in y, and so on.  This is useful primarily as indices into quadtrees.
@@ -134,6 +157,32 @@ impl MortonPrefix {
             item.try_into().unwrap()
         })
     }
+
+    /// Mask which zeroes the bits of the code which aren't part of this prefix.
+    fn mask(&self) -> u32 {
+        (u64::MAX << self.first_valid_bit) as u32
+    }
+
+    /// Is the given code inside the region this prefix represents?
+    pub fn contains(&self, code: &MortonCode) -> bool {
+        (self.code & self.mask()) == (code.data & self.mask())
+    }
+
+    /// Iterate over all `(x, y)` tiles inside the region this prefix represents, in row-major order.
+    ///
+    /// Each fixed level of the prefix fixes one bit of x and one bit of y, so this yields `2^first_valid_bit` tiles.
+    /// For a full prefix that's one tile, but for the empty prefix it is the entire 65536x65536 space: this is meant
+    /// for small regions.
+    pub fn iter_tiles(&self) -> impl Iterator<Item = (u16, u16)> {
+        let (x0, y0) = MortonCode {
+            data: self.code & self.mask(),
+        }
+        .decode();
+        let (x0, y0) = (x0 as u32, y0 as u32);
+        // Each axis has `first_valid_bit / 2` free bits.
+        let side = 1u32 << (self.first_valid_bit / 2);
+        (y0..y0 + side).flat_map(move |y| (x0..x0 + side).map(move |x| (x as u16, y as u16)))
+    }
 }
 
 impl std::cmp::PartialEq for MortonPrefix {
@@ -191,6 +240,30 @@ mod tests {
         }
     }
 
+    proptest::proptest! {
+        #[test]
+        fn test_encode_decode_signed_inverse(x: i16, y: i16) {
+            let enc = MortonCode::encode_signed(x, y);
+            assert_eq!(enc.decode_signed(), (x, y));
+        }
+    }
+
+    #[test]
+    fn test_signed_bias() {
+        assert_eq!(
+            MortonCode::encode_signed(i16::MIN, i16::MIN),
+            MortonCode::encode(0, 0)
+        );
+        assert_eq!(
+            MortonCode::encode_signed(0, 0),
+            MortonCode::encode(32768, 32768)
+        );
+        assert_eq!(
+            MortonCode::encode_signed(i16::MAX, -1),
+            MortonCode::encode(u16::MAX, 32767)
+        );
+    }
+
     proptest::proptest! {
         #[test]
         fn test_quadrants_inverses(x: u16, y: u16) {
@@ -366,6 +439,41 @@ mod tests {
         }
     }
 
+    proptest::proptest! {
+        #[test]
+        fn test_iter_tiles(
+            x in proptest::arbitrary::any::<u16>(),
+            y in proptest::arbitrary::any::<u16>(),
+            free_levels in 0u8..=5,
+        ) {
+            let prefix = MortonPrefix {
+                code: MortonCode::encode(x, y).data,
+                first_valid_bit: free_levels * 2,
+            };
+
+            let tiles = prefix.iter_tiles().collect::<Vec<_>>();
+            proptest::prop_assert_eq!(tiles.len(), 1 << prefix.first_valid_bit);
+            for (tx, ty) in tiles.iter().copied() {
+                proptest::prop_assert!(prefix.contains(&MortonCode::encode(tx, ty)));
+            }
+            // The tile we built the prefix from is always in there.
+            proptest::prop_assert!(tiles.contains(&(x, y)));
+
+            let unique = tiles.iter().collect::<std::collections::HashSet<_>>();
+            proptest::prop_assert_eq!(unique.len(), tiles.len());
+        }
+    }
+
+    #[test]
+    fn test_iter_tiles_full_prefix() {
+        let prefix = MortonPrefix::from_code(MortonCode::encode(0x1234, 0xfedc));
+        assert_eq!(
+            prefix.iter_tiles().collect::<Vec<_>>(),
+            vec![(0x1234, 0xfedc)]
+        );
+        assert!(!prefix.contains(&MortonCode::encode(0x1235, 0xfedc)));
+    }
+
     proptest::proptest! {
         #[test]
         fn test_prefix_hashing_fuzz(prefixes: Vec<MortonPrefix>) {
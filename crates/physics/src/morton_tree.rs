@@ -130,6 +130,33 @@ impl<T> MortonTree<T> {
         Some(self.value_slab.remove(vk.get_key()))
     }
 
+    /// Collect the values in the subtree rooted at `prefix`, descending at most `max_depth` levels below it.
+    ///
+    /// A `max_depth` of 0 returns only the value at `prefix`, if any.  Values are in depth-first order, parents before
+    /// children and children in quadrant order.
+    pub fn collect_subtree(&self, prefix: &MortonPrefix, max_depth: usize) -> Vec<&T> {
+        let mut out = vec![];
+        if let Some(node) = self.slab_ref_for_node(prefix) {
+            self.collect_subtree_inner(node, max_depth, &mut out);
+        }
+        out
+    }
+
+    fn collect_subtree_inner<'a>(&'a self, node: SlabRef, remaining: usize, out: &mut Vec<&'a T>) {
+        let n = &self.node_slab[node.get_key()];
+        if let Some(v) = n.value {
+            out.push(&self.value_slab[v.get_key()]);
+        }
+
+        if remaining == 0 {
+            return;
+        }
+
+        for c in n.children.iter().flatten() {
+            self.collect_subtree_inner(*c, remaining - 1, out);
+        }
+    }
+
     pub fn clear(&mut self) {
         self.root = None;
         self.node_slab.clear();
@@ -201,4 +228,41 @@ mod tests {
 
         }
     }
+
+    #[test]
+    fn test_collect_subtree_depth() {
+        let prefix_of = |a: (u16, u16), b: (u16, u16)| {
+            MortonPrefix::from_code(MortonCode::encode(a.0, a.1))
+                .merge(MortonPrefix::from_code(MortonCode::encode(b.0, b.1)))
+        };
+
+        // The 4x4 block at the origin, and then progressively deeper nodes inside it.
+        let block = prefix_of((0, 0), (3, 3));
+        let quad = prefix_of((0, 0), (1, 1));
+        let tile = prefix_of((0, 0), (0, 0));
+        let far_tile = prefix_of((3, 3), (3, 3));
+        let outside = prefix_of((4, 4), (4, 4));
+
+        let mut tree = MortonTree::new();
+        tree.insert(&block, "block");
+        tree.insert(&quad, "quad");
+        tree.insert(&tile, "tile");
+        tree.insert(&far_tile, "far_tile");
+        tree.insert(&outside, "outside");
+
+        assert_eq!(tree.collect_subtree(&block, 0), vec![&"block"]);
+        assert_eq!(tree.collect_subtree(&block, 1), vec![&"block", &"quad"]);
+        assert_eq!(
+            tree.collect_subtree(&block, 2),
+            vec![&"block", &"quad", &"tile", &"far_tile"]
+        );
+        assert_eq!(
+            tree.collect_subtree(&block, 100),
+            vec![&"block", &"quad", &"tile", &"far_tile"]
+        );
+        assert_eq!(tree.collect_subtree(&quad, 1), vec![&"quad", &"tile"]);
+        assert!(tree
+            .collect_subtree(&prefix_of((8, 8), (8, 8)), 16)
+            .is_empty());
+    }
 }
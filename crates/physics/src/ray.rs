@@ -8,6 +8,7 @@ pub struct Ray {
 }
 
 impl Ray {
+    /// Build a ray from an origin, a length, and an angle in radians counterclockwise from the positive x axis.
     pub fn from_angle(origin: V2<f64>, length: f64, theta: f64) -> Ray {
         Ray::new(
             origin,
@@ -39,19 +40,9 @@ impl Ray {
     }
 
     pub fn get_bounding_box(&self) -> Aabb<f64> {
-        let x0 = self.origin.x;
-        let y0 = self.origin.y;
-        let x1 = self.origin.x + self.length * self.direction.x;
-        let y1 = self.origin.y + self.direction.y * self.length;
-        let p1 = V2 {
-            x: x0.min(x1),
-            y: y0.min(y1),
-        };
-        let p2 = V2 {
-            x: x0.max(x1),
-            y: y0.max(y1),
-        };
-        Aabb::from_points(p1, p2).expect("This internal logic should never fail")
+        let end = self.endpoint();
+        Aabb::from_points(self.origin.min(end), self.origin.max(end))
+            .expect("This internal logic should never fail")
     }
 
     /// Evaluate the ray at a given `t`.
@@ -61,6 +52,36 @@ impl Ray {
             self.origin.y + self.direction.y * t,
         )
     }
+
+    /// The point at the far end of the ray, e.g. `evaluate(length)`.
+    pub fn endpoint(&self) -> V2<f64> {
+        self.evaluate(self.length)
+    }
+
+    /// Intersect this ray with another, treating both as finite segments.
+    ///
+    /// Returns `None` if the segments don't cross or if they are parallel.  Collinear segments which overlap are also
+    /// `None`: they share a range of points rather than one, and there is no good answer for which to return.
+    /// Segments which touch only at an endpoint do intersect.
+    pub fn intersect_ray(&self, other: &Ray) -> Option<V2<f64>> {
+        fn cross(a: V2<f64>, b: V2<f64>) -> f64 {
+            a.x * b.y - a.y * b.x
+        }
+
+        let denom = cross(self.direction, other.direction);
+        if denom.abs() < 1e-12 {
+            return None;
+        }
+
+        let delta = other.origin - self.origin;
+        let t = cross(delta, other.direction) / denom;
+        let u = cross(delta, self.direction) / denom;
+        if !(0.0..=self.length).contains(&t) || !(0.0..=other.length).contains(&u) {
+            return None;
+        }
+
+        Some(self.evaluate(t))
+    }
 }
 
 #[cfg(test)]
@@ -76,6 +97,24 @@ mod tests {
         assert_eq!(test, correct);
     }
 
+    #[test]
+    fn from_angle_is_normalized() {
+        for i in 0..16 {
+            let theta = i as f64 * std::f64::consts::PI / 8.0;
+            let r = Ray::from_angle(V2::new(1.0, 2.0), 5.0, theta);
+            assert_relative_eq!(r.direction.length(), 1.0);
+        }
+    }
+
+    #[test]
+    fn test_endpoint() {
+        let r = Ray::from_angle(V2::new(1.0, 2.0), 5.0, 0.7);
+        let expected = r.origin + r.direction * r.length;
+        assert_relative_eq!(r.endpoint().x, expected.x);
+        assert_relative_eq!(r.endpoint().y, expected.y);
+        assert_eq!(r.endpoint(), r.evaluate(r.length));
+    }
+
     #[test]
     fn test_bounding_box() {
         let r = Ray::new(V2::new(1.0, 1.0), V2::new(1.0, 1.0).normalize(), 3.0);
@@ -95,4 +134,40 @@ mod tests {
         assert_relative_eq!(aabb.get_p2().x, -1.0);
         assert_relative_eq!(aabb.get_p2().y, -1.0);
     }
+
+    #[test]
+    fn test_intersect_ray_crossing() {
+        let r1 = Ray::from_points(V2::new(0.0, 0.0), V2::new(2.0, 2.0));
+        let r2 = Ray::from_points(V2::new(0.0, 2.0), V2::new(2.0, 0.0));
+        let p = r1.intersect_ray(&r2).expect("Should cross");
+        assert_relative_eq!(p.x, 1.0);
+        assert_relative_eq!(p.y, 1.0);
+        let p = r2.intersect_ray(&r1).expect("Should cross");
+        assert_relative_eq!(p.x, 1.0);
+        assert_relative_eq!(p.y, 1.0);
+
+        // Would cross if r2 were longer.
+        let r2 = Ray::from_points(V2::new(0.0, 2.0), V2::new(0.5, 1.5));
+        assert!(r1.intersect_ray(&r2).is_none());
+    }
+
+    #[test]
+    fn test_intersect_ray_parallel() {
+        let r1 = Ray::new(V2::new(0.0, 0.0), V2::new(1.0, 0.0), 5.0);
+        let r2 = Ray::new(V2::new(0.0, 1.0), V2::new(1.0, 0.0), 5.0);
+        assert!(r1.intersect_ray(&r2).is_none());
+
+        // Collinear and overlapping is ambiguous.
+        let r3 = Ray::new(V2::new(2.0, 0.0), V2::new(1.0, 0.0), 5.0);
+        assert!(r1.intersect_ray(&r3).is_none());
+    }
+
+    #[test]
+    fn test_intersect_ray_touching_endpoint() {
+        let r1 = Ray::new(V2::new(0.0, 0.0), V2::new(1.0, 0.0), 2.0);
+        let r2 = Ray::new(V2::new(2.0, -1.0), V2::new(0.0, 1.0), 1.0);
+        let p = r1.intersect_ray(&r2).expect("Endpoints touch");
+        assert_relative_eq!(p.x, 2.0);
+        assert_relative_eq!(p.y, 0.0);
+    }
 }
@@ -0,0 +1,156 @@
+//! Raycasting against a grid of solid and empty cells.
+use crate::raycasting::*;
+
+/// The first solid cell hit by [raycast_grid].
+#[derive(Debug)]
+pub struct GridHit {
+    /// The cell, in grid coordinates.
+    pub cell: (i64, i64),
+    /// The `t` along the ray at which it entered the cell.
+    pub t: f64,
+    pub result: RaycastingResult,
+}
+
+/// Walk the cells of a grid with square cells of side `cell_size` along the ray, returning the first one for which
+/// `is_solid` returns true.
+///
+/// Cell `(x, y)` covers `[x * cell_size, (x + 1) * cell_size)` on each axis.  The normal is that of the face through
+/// which the ray entered the cell.  If the ray starts inside a solid cell, the hit is at the origin and has no normal,
+/// as with the other raycasting tests.
+///
+/// # Panics
+///
+/// If `cell_size` isn't positive: a zero size would walk forever, and a negative one would mirror the grid.
+pub fn raycast_grid(
+    ray: &Ray,
+    cell_size: f64,
+    is_solid: impl Fn(i64, i64) -> bool,
+) -> Option<GridHit> {
+    assert!(
+        cell_size > 0.0,
+        "Cell size must be positive, got {}",
+        cell_size
+    );
+
+    // Scaling the origin and length but not the direction means that `t` in grid space is `t / cell_size` in world
+    // space.
+    let grid_ray = Ray::new(
+        ray.origin / cell_size,
+        ray.direction,
+        ray.length / cell_size,
+    );
+    let mut cells = grid_ray.raycast();
+    let mut prev: Option<(i64, i64)> = None;
+
+    while let Some(cell) = cells.next() {
+        if !is_solid(cell.0, cell.1) {
+            prev = Some(cell);
+            continue;
+        }
+
+        let t = cells.current_t() * cell_size;
+        let result = match prev {
+            None => RaycastingResult {
+                point: ray.origin,
+                normal: None,
+                inside: true,
+            },
+            Some(p) => RaycastingResult {
+                point: ray.evaluate(t),
+                normal: Some(V2::new((p.0 - cell.0) as f64, (p.1 - cell.1) as f64)),
+                inside: false,
+            },
+        };
+        return Some(GridHit { cell, t, result });
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_faces() {
+        // A single solid cell at (2, 2), with cells of size 0.5, so it covers [1, 1.5) on both axes.  We shoot at it
+        // from each side.
+        let is_solid = |x, y| (x, y) == (2, 2);
+        for (origin, direction, expected_normal, expected_point) in [
+            (
+                V2::new(0.0, 1.25),
+                V2::new(1.0, 0.0),
+                V2::new(-1.0, 0.0),
+                V2::new(1.0, 1.25),
+            ),
+            (
+                V2::new(3.0, 1.25),
+                V2::new(-1.0, 0.0),
+                V2::new(1.0, 0.0),
+                V2::new(1.5, 1.25),
+            ),
+            (
+                V2::new(1.2, 0.0),
+                V2::new(0.0, 1.0),
+                V2::new(0.0, -1.0),
+                V2::new(1.2, 1.0),
+            ),
+            (
+                V2::new(1.2, 3.0),
+                V2::new(0.0, -1.0),
+                V2::new(0.0, 1.0),
+                V2::new(1.2, 1.5),
+            ),
+        ] {
+            let ray = Ray::new(origin, direction, 10.0);
+            let hit = raycast_grid(&ray, 0.5, is_solid).expect("Should hit");
+            assert_eq!(hit.cell, (2, 2));
+            assert!(!hit.result.inside);
+            assert_eq!(hit.result.normal, Some(expected_normal));
+            assert_relative_eq!(hit.result.point.x, expected_point.x, epsilon = 1e-9);
+            assert_relative_eq!(hit.result.point.y, expected_point.y, epsilon = 1e-9);
+            assert_relative_eq!(hit.t, origin.distance(&expected_point), epsilon = 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_diagonal() {
+        // Moving up and to the right toward a wall at x = 3, we should hit its left face.
+        let ray = Ray::from_points(V2::new(0.5, 0.5), V2::new(10.5, 3.0));
+        let hit = raycast_grid(&ray, 1.0, |x, _| x >= 3).expect("Should hit");
+        assert_eq!(hit.cell.0, 3);
+        assert_eq!(hit.result.normal, Some(V2::new(-1.0, 0.0)));
+        assert_relative_eq!(hit.result.point.x, 3.0, epsilon = 1e-9);
+        assert_relative_eq!(hit.result.point.y, 0.5 + 2.5 * 0.25, epsilon = 1e-9);
+    }
+
+    #[test]
+    fn test_inside() {
+        let ray = Ray::new(V2::new(1.5, 1.5), V2::new(1.0, 0.0), 5.0);
+        let hit = raycast_grid(&ray, 1.0, |_, _| true).expect("Should hit");
+        assert_eq!(hit.cell, (1, 1));
+        assert!(hit.result.inside);
+        assert_eq!(hit.result.normal, None);
+        assert_eq!(hit.result.point, ray.origin);
+        assert_eq!(hit.t, 0.0);
+    }
+
+    #[test]
+    fn test_miss() {
+        let ray = Ray::new(V2::new(0.5, 0.5), V2::new(1.0, 1.0).normalize(), 20.0);
+        assert!(raycast_grid(&ray, 1.0, |_, _| false).is_none());
+
+        // The solid cell is past the end of the ray.
+        let ray = Ray::new(V2::new(0.5, 0.5), V2::new(1.0, 0.0), 2.0);
+        assert!(raycast_grid(&ray, 1.0, |x, y| (x, y) == (3, 0)).is_none());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_cell_size() {
+        let ray = Ray::new(V2::new(0.5, 0.5), V2::new(1.0, 0.0), 2.0);
+        raycast_grid(&ray, 0.0, |_, _| false);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::*;
 
+pub(crate) mod grid;
 pub(crate) mod ray_aabb;
 pub mod tile_grid;
 
@@ -14,4 +15,5 @@ pub struct RaycastingResult {
     pub inside: bool,
 }
 
+pub use grid::*;
 pub use tile_grid::*;
@@ -11,6 +11,35 @@ pub struct TileGridRaycastPointIterator {
     x_inc: i64,
     y_inc: i64,
     error: f64,
+    /// If set, inclusive `(min, max)` cells outside of which we stop.
+    bounds: Option<((i64, i64), (i64, i64))>,
+    /// Whether we have yielded a cell inside `bounds` yet.
+    entered: bool,
+    /// The `t` at which the ray enters the cell we will yield next.
+    next_cell_t: f64,
+    /// The `t` at which the ray entered the cell we yielded last.
+    last_cell_t: f64,
+    /// The `t` of the next vertical and horizontal grid lines the ray crosses.
+    t_next_x: f64,
+    t_next_y: f64,
+    /// How much `t` increases between grid lines on each axis.
+    t_delta_x: f64,
+    t_delta_y: f64,
+}
+
+/// Get the range of `t` over which a ray's coordinate on one axis is in `[lo, hi)`, or `None` if it never is.
+fn axis_t_range(origin: f64, direction: f64, lo: f64, hi: f64) -> Option<(f64, f64)> {
+    if direction == 0.0 {
+        if lo <= origin && origin < hi {
+            Some((f64::NEG_INFINITY, f64::INFINITY))
+        } else {
+            None
+        }
+    } else {
+        let t1 = (lo - origin) / direction;
+        let t2 = (hi - origin) / direction;
+        Some((t1.min(t2), t1.max(t2)))
+    }
 }
 
 impl TileGridRaycastPointIterator {
@@ -28,31 +57,42 @@ impl TileGridRaycastPointIterator {
         let x_inc: i64;
         let y_inc: i64;
         let mut error: f64;
+        // The `t` values track the error term: it is positive exactly when the next horizontal line comes first.
+        let t_delta_x = 1.0 / ray.direction.x.abs();
+        let t_delta_y = 1.0 / ray.direction.y.abs();
+        let t_next_x: f64;
+        let t_next_y: f64;
 
         if dx == 0.0 {
             x_inc = 0;
             error = f64::INFINITY;
+            t_next_x = f64::INFINITY;
         } else if x1 > x0 {
             x_inc = 1;
             n += x1.floor() as i64 - x;
             error = (x0.floor() + 1.0 - x0) * dy;
+            t_next_x = (x0.floor() + 1.0 - x0) * t_delta_x;
         } else {
             x_inc = -1;
             n += x - x1.floor() as i64;
             error = (x0 - x0.floor()) * dy;
+            t_next_x = (x0 - x0.floor()) * t_delta_x;
         }
 
         if dy == 0.0 {
             y_inc = 0;
             error -= f64::INFINITY;
+            t_next_y = f64::INFINITY;
         } else if y1 > y0 {
             y_inc = 1;
             n += y1.floor() as i64 - y;
             error -= (y0.floor() + 1.0 - y0) * dx;
+            t_next_y = (y0.floor() + 1.0 - y0) * t_delta_y;
         } else {
             y_inc = -1;
             n += y - y1.floor() as i64;
             error -= (y0 - y0.floor()) * dx;
+            t_next_y = (y0 - y0.floor()) * t_delta_y;
         }
 
         TileGridRaycastPointIterator {
@@ -64,28 +104,128 @@ impl TileGridRaycastPointIterator {
             x_inc,
             y_inc,
             error,
+            bounds: None,
+            entered: false,
+            next_cell_t: 0.0,
+            last_cell_t: 0.0,
+            t_next_x,
+            t_next_y,
+            t_delta_x,
+            t_delta_y,
         }
     }
-}
 
-impl Iterator for TileGridRaycastPointIterator {
-    type Item = (i64, i64);
-    fn next(&mut self) -> Option<Self::Item> {
+    /// Like [TileGridRaycastPointIterator::new], but only yield cells inside the inclusive bounds `min` to `max`,
+    /// stopping once the ray leaves them.
+    ///
+    /// The ray is clipped to the bounds first, so long rays which are mostly outside the grid don't cost anything for
+    /// the part outside.
+    pub fn new_clamped(
+        ray: &Ray,
+        min: (i64, i64),
+        max: (i64, i64),
+    ) -> TileGridRaycastPointIterator {
+        // The cells cover `[min, max + 1)` in continuous coordinates.
+        let clipped = axis_t_range(
+            ray.origin.x,
+            ray.direction.x,
+            min.0 as f64,
+            max.0 as f64 + 1.0,
+        )
+        .zip(axis_t_range(
+            ray.origin.y,
+            ray.direction.y,
+            min.1 as f64,
+            max.1 as f64 + 1.0,
+        ))
+        .and_then(|((txmin, txmax), (tymin, tymax))| {
+            let t_enter = txmin.max(tymin).max(0.0);
+            let t_exit = txmax.min(tymax).min(ray.length);
+            (t_enter <= t_exit).then(|| {
+                (
+                    Ray::new(ray.evaluate(t_enter), ray.direction, t_exit - t_enter),
+                    t_enter,
+                )
+            })
+        });
+
+        let mut ret = match clipped {
+            Some((r, t_enter)) => {
+                // Keep reporting `t` along the original ray, not the clipped one.
+                let mut it = TileGridRaycastPointIterator::new(&r);
+                it.next_cell_t += t_enter;
+                it.t_next_x += t_enter;
+                it.t_next_y += t_enter;
+                it
+            }
+            None => {
+                // The original ray may be arbitrarily long, which `new` can't count cells for, so start from a
+                // zero-length one.
+                let mut empty =
+                    TileGridRaycastPointIterator::new(&Ray::new(ray.origin, ray.direction, 0.0));
+                empty.n = 0;
+                empty
+            }
+        };
+        ret.bounds = Some((min, max));
+        ret
+    }
+
+    fn next_unclamped(&mut self) -> Option<(i64, i64)> {
         if self.n <= 0 {
             return None;
         }
 
         self.n -= 1;
         let (x, y) = (self.x, self.y);
+        self.last_cell_t = self.next_cell_t;
         if self.error > 0.0 {
             self.y += self.y_inc;
             self.error -= self.dx;
+            self.next_cell_t = self.t_next_y;
+            self.t_next_y += self.t_delta_y;
         } else {
             self.x += self.x_inc;
             self.error += self.dy;
+            self.next_cell_t = self.t_next_x;
+            self.t_next_x += self.t_delta_x;
         }
         Some((x, y))
     }
+
+    /// The `t` along the ray at which it entered the cell most recently returned by [Iterator::next], e.g. for
+    /// weighting cells by distance.
+    ///
+    /// The first cell of an unclamped ray is at 0.  For a direction which isn't normalized, `t` is in units of the
+    /// direction's length, as with [Ray::evaluate].
+    pub fn current_t(&self) -> f64 {
+        self.last_cell_t
+    }
+}
+
+impl Iterator for TileGridRaycastPointIterator {
+    type Item = (i64, i64);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (x, y) = self.next_unclamped()?;
+            let (min, max) = match self.bounds {
+                Some(b) => b,
+                None => return Some((x, y)),
+            };
+
+            if (min.0..=max.0).contains(&x) && (min.1..=max.1).contains(&y) {
+                self.entered = true;
+                return Some((x, y));
+            }
+
+            // Because we clipped the ray, we're at most a cell or two away from the bounds here, either because the
+            // ray started exactly on an edge or because it has now left.
+            if self.entered {
+                self.n = 0;
+                return None;
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -115,4 +255,127 @@ mod tests {
             assert_eq!(test, correct);
         }
     }
+
+    #[test]
+    fn test_clamped_stops_at_boundary() {
+        let ray = Ray::new(V2::new(0.5, 0.5), V2::new(1.0, 0.0), 100.0);
+        let test =
+            TileGridRaycastPointIterator::new_clamped(&ray, (0, 0), (5, 5)).collect::<Vec<_>>();
+        let correct = (0..=5).map(|x| (x, 0)).collect::<Vec<_>>();
+        assert_eq!(test, correct);
+    }
+
+    #[test]
+    fn test_clamped_enters_from_outside() {
+        let ray = Ray::new(V2::new(-10.5, 2.5), V2::new(1.0, 0.0), 100.0);
+        let test =
+            TileGridRaycastPointIterator::new_clamped(&ray, (0, 0), (3, 3)).collect::<Vec<_>>();
+        assert_eq!(test, vec![(0, 2), (1, 2), (2, 2), (3, 2)]);
+    }
+
+    #[test]
+    fn test_clamped_miss() {
+        let ray = Ray::new(V2::new(-5.5, 1.5), V2::new(-1.0, 0.0), 100.0);
+        assert_eq!(
+            TileGridRaycastPointIterator::new_clamped(&ray, (0, 0), (3, 3)).count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_clamped_infinite() {
+        let miss = Ray::new(V2::new(10.5, 10.5), V2::new(-1.0, 0.0), f64::INFINITY);
+        assert_eq!(
+            TileGridRaycastPointIterator::new_clamped(&miss, (0, 0), (5, 5)).count(),
+            0
+        );
+
+        let hit = Ray::new(V2::new(10.5, 2.5), V2::new(-1.0, 0.0), f64::INFINITY);
+        assert_eq!(
+            TileGridRaycastPointIterator::new_clamped(&hit, (0, 0), (5, 5)).collect::<Vec<_>>(),
+            (0..=5).rev().map(|x| (x, 2)).collect::<Vec<_>>()
+        );
+
+        let diagonal = Ray::new(V2::new(-0.5, -0.25), V2::new(1.0, 1.0).normalize(), 1e19);
+        let cells = TileGridRaycastPointIterator::new_clamped(&diagonal, (0, 0), (5, 5))
+            .collect::<Vec<_>>();
+        assert_eq!(cells.first(), Some(&(0, 0)));
+        assert_eq!(cells.last(), Some(&(5, 5)));
+    }
+
+    #[test]
+    fn test_clamped_max_bounds() {
+        let ray = Ray::new(V2::new(0.5, 0.5), V2::new(1.0, 1.0).normalize(), 2.0);
+        let test = TileGridRaycastPointIterator::new_clamped(&ray, (0, 0), (i64::MAX, i64::MAX))
+            .collect::<Vec<_>>();
+        assert_eq!(test, ray.raycast().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_clamped_inside_matches_unclamped() {
+        let rays = vec![
+            Ray::new(V2::new(1.25, 1.75), V2::new(1.0, 2.0).normalize(), 2.0),
+            Ray::new(V2::new(8.5, 8.5), V2::new(-1.0, -1.0).normalize(), 5.0),
+            Ray::new(V2::new(5.0, 5.0), V2::new(0.0, -1.0), 3.5),
+        ];
+
+        for ray in rays {
+            let unclamped = ray.raycast().collect::<Vec<_>>();
+            let clamped = TileGridRaycastPointIterator::new_clamped(&ray, (0, 0), (10, 10))
+                .collect::<Vec<_>>();
+            assert_eq!(clamped, unclamped, "{:?}", ray);
+        }
+    }
+
+    #[test]
+    fn test_current_t_axis_aligned() {
+        let mut it = Ray::new(V2::new(0.5, 0.5), V2::new(1.0, 0.0), 4.0).raycast();
+        let mut got = vec![];
+        while let Some(cell) = it.next() {
+            got.push((cell, it.current_t()));
+        }
+        assert_eq!(
+            got,
+            vec![
+                ((0, 0), 0.0),
+                ((1, 0), 0.5),
+                ((2, 0), 1.5),
+                ((3, 0), 2.5),
+                ((4, 0), 3.5)
+            ]
+        );
+
+        let mut it = Ray::new(V2::new(2.25, 0.5), V2::new(0.0, -1.0), 2.0).raycast();
+        let mut got = vec![];
+        while let Some(cell) = it.next() {
+            got.push((cell, it.current_t()));
+        }
+        assert_eq!(got, vec![((2, 0), 0.0), ((2, -1), 0.5), ((2, -2), 1.5)]);
+    }
+
+    #[test]
+    fn test_current_t_diagonal_is_monotonic() {
+        let ray = Ray::new(V2::new(0.3, 0.8), V2::new(3.0, 1.0).normalize(), 10.0);
+        let mut it = ray.raycast();
+        let mut last = -1.0;
+        while let Some((x, y)) = it.next() {
+            let t = it.current_t();
+            assert!(t > last, "{} {}", t, last);
+            last = t;
+
+            // Just past the entry point, the ray is inside the yielded cell.
+            let p = ray.evaluate(t + 1e-9);
+            assert_eq!((p.x.floor() as i64, p.y.floor() as i64), (x, y));
+        }
+    }
+
+    #[test]
+    fn test_current_t_clamped() {
+        let ray = Ray::new(V2::new(-10.5, 2.5), V2::new(1.0, 0.0), 100.0);
+        let mut it = TileGridRaycastPointIterator::new_clamped(&ray, (0, 0), (3, 3));
+        assert_eq!(it.next(), Some((0, 2)));
+        assert_eq!(it.current_t(), 10.5);
+        assert_eq!(it.next(), Some((1, 2)));
+        assert_eq!(it.current_t(), 11.5);
+    }
 }
@@ -1,12 +1,19 @@
 //! A 2-dimensional vector/point.
 use num::Num;
 
-#[derive(Debug, Copy, Clone, Eq, Ord, PartialEq, PartialOrd)]
+#[derive(Debug, Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
 pub struct V2<T> {
     pub x: T,
     pub y: T,
 }
 
+/// A float position quantized to an integer cell, for use as a key in hashmaps and the like.
+///
+/// Positions are divided by the resolution and floored, so every point in `[n * resolution, (n + 1) * resolution)` maps
+/// to cell `n` on each axis.
+#[derive(Debug, Copy, Clone, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct QuantizedV2(pub V2<i64>);
+
 impl<T: Num> V2<T> {
     pub const fn new(x: T, y: T) -> Self {
         V2 { x, y }
@@ -59,6 +66,59 @@ where
     }
 }
 
+/// The smaller of two values, where NaN follows [f64::min]: if only one side is NaN, the other side is returned.
+fn partial_min<T: PartialOrd>(a: T, b: T) -> T {
+    match a.partial_cmp(&b) {
+        Some(std::cmp::Ordering::Greater) => b,
+        Some(_) => a,
+        // Only NaN is unordered with itself.
+        None if a.partial_cmp(&a).is_none() => b,
+        None => a,
+    }
+}
+
+/// The larger of two values, with the same NaN handling as [partial_min].
+fn partial_max<T: PartialOrd>(a: T, b: T) -> T {
+    match a.partial_cmp(&b) {
+        Some(std::cmp::Ordering::Less) => b,
+        Some(_) => a,
+        None if a.partial_cmp(&a).is_none() => b,
+        None => a,
+    }
+}
+
+/// Component-wise operations.
+///
+/// These take `self` by value so that they're found before the lexicographic versions from [Ord], which `V2` also
+/// has.  For floats, NaN behaves as it does with [f64::min] and [f64::max]: a NaN component loses to a non-NaN one.
+impl<T: PartialOrd + Copy> V2<T> {
+    /// The component-wise minimum of two vectors.
+    #[must_use = "This function doesn't modify the vector in place"]
+    pub fn min(self, other: V2<T>) -> V2<T> {
+        V2 {
+            x: partial_min(self.x, other.x),
+            y: partial_min(self.y, other.y),
+        }
+    }
+
+    /// The component-wise maximum of two vectors.
+    #[must_use = "This function doesn't modify the vector in place"]
+    pub fn max(self, other: V2<T>) -> V2<T> {
+        V2 {
+            x: partial_max(self.x, other.x),
+            y: partial_max(self.y, other.y),
+        }
+    }
+
+    /// Clamp each component to the range given by the components of `lo` and `hi`.
+    ///
+    /// If `lo` is greater than `hi` on some axis, `lo` wins.
+    #[must_use = "This function doesn't modify the vector in place"]
+    pub fn clamp(self, lo: V2<T>, hi: V2<T>) -> V2<T> {
+        self.min(hi).max(lo)
+    }
+}
+
 impl<T: Num> std::ops::Add for V2<T> {
     type Output = V2<T>;
 
@@ -133,3 +193,97 @@ impl<T: Num + Copy> std::ops::SubAssign for V2<T> {
         self.y = self.y - rhs.y;
     }
 }
+
+impl QuantizedV2 {
+    /// Quantize `point` to the cell of size `resolution` containing it.
+    ///
+    /// `resolution` must be positive.  This is only checked in debug builds: in release, zero or negative resolutions
+    /// produce meaningless cells rather than panicking.
+    pub fn new(point: V2<f64>, resolution: f64) -> QuantizedV2 {
+        debug_assert!(
+            resolution > 0.0,
+            "Resolution must be positive, got {}",
+            resolution
+        );
+        QuantizedV2(V2::new(
+            (point.x / resolution).floor() as i64,
+            (point.y / resolution).floor() as i64,
+        ))
+    }
+
+    /// The lower left corner of this cell, in the original coordinate space.
+    pub fn cell_origin(&self, resolution: f64) -> V2<f64> {
+        V2::new(self.0.x as f64 * resolution, self.0.y as f64 * resolution)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_max_clamp() {
+        let a = V2::new(1u16, 5);
+        let b = V2::new(3u16, 2);
+        assert_eq!(a.min(b), V2::new(1, 2));
+        assert_eq!(a.max(b), V2::new(3, 5));
+        assert_eq!(
+            V2::new(-5.0, 5.0).clamp(V2::new(0.0, 0.0), V2::new(2.0, 2.0)),
+            V2::new(0.0, 2.0)
+        );
+        assert_eq!(
+            V2::new(1.0, 1.5).clamp(V2::new(0.0, 0.0), V2::new(2.0, 2.0)),
+            V2::new(1.0, 1.5)
+        );
+    }
+
+    #[test]
+    fn test_min_max_nan() {
+        let nan = V2::new(f64::NAN, 1.0);
+        let other = V2::new(2.0, f64::NAN);
+        assert_eq!(nan.min(other), V2::new(2.0, 1.0));
+        assert_eq!(other.min(nan), V2::new(2.0, 1.0));
+        assert_eq!(nan.max(other), V2::new(2.0, 1.0));
+        assert_eq!(other.max(nan), V2::new(2.0, 1.0));
+
+        let both = V2::new(f64::NAN, f64::NAN).min(V2::new(f64::NAN, f64::NAN));
+        assert!(both.x.is_nan() && both.y.is_nan());
+    }
+
+    #[test]
+    fn test_integer_hashmap_key() {
+        let mut map = std::collections::HashMap::new();
+        map.insert(V2::new(1i64, -2), "a");
+        map.insert(V2::new(-2i64, 1), "b");
+        assert_eq!(map.get(&V2::new(1, -2)), Some(&"a"));
+        assert_eq!(map.get(&V2::new(-2, 1)), Some(&"b"));
+        assert_eq!(map.get(&V2::new(1, 1)), None);
+    }
+
+    #[test]
+    fn test_quantized() {
+        let a = QuantizedV2::new(V2::new(1.1, 2.9), 0.5);
+        let b = QuantizedV2::new(V2::new(1.4, 2.6), 0.5);
+        assert_eq!(a, b);
+        assert_eq!(a, QuantizedV2(V2::new(2, 5)));
+        assert_eq!(a.cell_origin(0.5), V2::new(1.0, 2.5));
+
+        // Negative coordinates floor rather than truncating toward zero.
+        assert_eq!(
+            QuantizedV2::new(V2::new(-0.1, 0.1), 1.0),
+            QuantizedV2(V2::new(-1, 0))
+        );
+
+        let mut map = std::collections::HashMap::new();
+        map.insert(a, "bucket");
+        assert_eq!(map.get(&b), Some(&"bucket"));
+        assert_eq!(map.get(&QuantizedV2::new(V2::new(1.6, 2.6), 0.5)), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn test_quantize_zero_resolution() {
+        QuantizedV2::new(V2::new(1.0, 1.0), 0.0);
+    }
+}